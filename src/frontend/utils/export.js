@@ -77,6 +77,44 @@ function generateFilename(ext, envContext = null) {
   return `claude-confirm-${date}-${time}.${ext}`
 }
 
+/**
+ * 將環境上下文整理成文字行（客戶端、專案、路徑、session、Git 狀態）
+ * @param {Object} envContext - 環境上下文
+ * @returns {string[]}
+ */
+function formatEnvContext(envContext) {
+  if (!envContext) return []
+
+  const lines = []
+  const client = envContext.client
+  if (client) lines.push(`Client: ${client.title || client.name}${client.version ? ` ${client.version}` : ''}`)
+  if (envContext.project_name) lines.push(`Project: ${envContext.project_name}`)
+  if (envContext.cwd) lines.push(`Directory: \`${envContext.cwd}\``)
+
+  const session = envContext.session
+  if (session) {
+    if (session.hostname) lines.push(`Host: ${session.user ? `${session.user}@` : ''}${session.hostname}`)
+    if (session.ssh) lines.push(`SSH: ${session.ssh}`)
+    if (session.tmux) lines.push(`tmux: ${session.tmux.target || session.tmux.pane_id}`)
+    if (session.screen) lines.push(`screen: ${session.screen}`)
+    if (session.container) lines.push(`Container: ${session.container}`)
+    if (session.process_chain && session.process_chain.length > 0) {
+      lines.push(`Processes: ${session.process_chain.map(p => `${p.name} (${p.pid})`).join(' ← ')}`)
+    }
+  }
+
+  const git = envContext.git
+  if (git) {
+    let branch = `Git: ${git.branch || 'detached'}`
+    if (git.head) branch += ` @ ${git.head}`
+    if (git.dirty_files) branch += `, ${git.dirty_files} changed file(s)`
+    if (git.ahead || git.behind) branch += `, ↑${git.ahead || 0} ↓${git.behind || 0}`
+    lines.push(branch)
+  }
+
+  return lines
+}
+
 /**
  * 匯出為 PNG
  * @param {HTMLElement} element - 要截圖的元素
@@ -90,13 +128,11 @@ export async function exportToPNG(element, isDark, envContext = null) {
   const base64Data = dataUrl.replace(/^data:image\/png;base64,/, '')
   const filename = generateFilename('png', envContext)
 
-  await invoke('save_export_file', {
+  return await invoke('save_export_file', {
     filename,
     data: base64Data,
     fileType: 'png'
   })
-
-  return filename
 }
 
 /**
@@ -141,25 +177,29 @@ export async function exportToPDF(element, isDark, envContext = null) {
 
   const filename = generateFilename('pdf', envContext)
 
-  await invoke('save_export_file', {
+  return await invoke('save_export_file', {
     filename,
     data: base64Data,
     fileType: 'pdf'
   })
-
-  return filename
 }
 
 /**
- * 匯出為 Markdown（支援 base64 圖片內嵌）
+ * 組合完整的 Markdown 摘要（內容、環境資訊與段落）
  * @param {string} markdownContent - Markdown 內容
  * @param {Array} sections - 段落列表
  * @param {Object} envContext - 環境上下文
+ * @returns {string} Markdown 文字
  */
-export async function exportToMarkdown(markdownContent, sections = [], envContext = null) {
-  // 組合完整的 markdown 內容
+export function buildMarkdown(markdownContent, sections = [], envContext = null) {
   let fullContent = markdownContent
 
+  // 附加環境資訊
+  const envLines = formatEnvContext(envContext)
+  if (envLines.length > 0) {
+    fullContent += '\n\n---\n\n## Environment\n\n' + envLines.map(line => `- ${line}`).join('\n') + '\n'
+  }
+
   // 如果有 sections，附加到末尾
   if (sections && sections.length > 0) {
     fullContent += '\n\n---\n\n## Sections\n\n'
@@ -169,17 +209,27 @@ export async function exportToMarkdown(markdownContent, sections = [], envContex
     })
   }
 
+  return fullContent
+}
+
+/**
+ * 匯出為 Markdown（支援 base64 圖片內嵌）
+ * @param {string} markdownContent - Markdown 內容
+ * @param {Array} sections - 段落列表
+ * @param {Object} envContext - 環境上下文
+ */
+export async function exportToMarkdown(markdownContent, sections = [], envContext = null) {
+  const fullContent = buildMarkdown(markdownContent, sections, envContext)
+
   // 轉換為 base64（保留 markdown 中的 base64 圖片語法）
   const base64Data = btoa(unescape(encodeURIComponent(fullContent)))
   const filename = generateFilename('md', envContext)
 
-  await invoke('save_export_file', {
+  return await invoke('save_export_file', {
     filename,
     data: base64Data,
     fileType: 'md'
   })
-
-  return filename
 }
 
 /**
@@ -190,20 +240,19 @@ export async function exportToMarkdown(markdownContent, sections = [], envContex
  * @param {string} markdownContent - Markdown 原始內容
  * @param {Array} sections - 段落列表
  * @param {Object} envContext - 環境上下文
- * @returns {Promise<string[]>} 匯出的檔名列表
+ * @returns {Promise<string[]>} 實際寫入的路徑（同名檔案存在時會加上後綴）
  */
 export async function exportAll(element, isDark, markdownContent, sections = [], envContext = null) {
   // 只截取一次 canvas，PNG 和 PDF 共用
   const canvas = await captureElementAsCanvas(element, isDark)
 
-  const [pngFilename, pdfFilename, mdFilename] = await Promise.all([
+  const [pngPath, pdfPath, mdPath] = await Promise.all([
     // PNG
     (async () => {
       const dataUrl = canvas.toDataURL('image/png')
       const base64Data = dataUrl.replace(/^data:image\/png;base64,/, '')
       const filename = generateFilename('png', envContext)
-      await invoke('save_export_file', { filename, data: base64Data, fileType: 'png' })
-      return filename
+      return invoke('save_export_file', { filename, data: base64Data, fileType: 'png' })
     })(),
     // PDF
     (async () => {
@@ -225,12 +274,11 @@ export async function exportAll(element, isDark, markdownContent, sections = [],
       const pdfOutput = pdf.output('datauristring')
       const base64Data = pdfOutput.split(',')[1]
       const filename = generateFilename('pdf', envContext)
-      await invoke('save_export_file', { filename, data: base64Data, fileType: 'pdf' })
-      return filename
+      return invoke('save_export_file', { filename, data: base64Data, fileType: 'pdf' })
     })(),
     // Markdown
     exportToMarkdown(markdownContent, sections, envContext)
   ])
 
-  return [pngFilename, pdfFilename, mdFilename]
+  return [pngPath, pdfPath, mdPath]
 }
@@ -1,4 +1,4 @@
-use claude_confirm::mcp::run_server;
+use claude_confirm::{cli, mcp::run_server};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -6,6 +6,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
         .init();
 
+    // 子命令（install / ask / hook 等）處理完即結束；失敗時以 EXIT_ERROR 結束，與取消（1）區分
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::run(&args).await {
+        Ok(Some(code)) => std::process::exit(code),
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("錯誤: {:#}", e);
+            std::process::exit(cli::EXIT_ERROR);
+        }
+    }
+
     log::info!("啟動 Claude Confirm MCP 服務器");
 
     run_server().await?;
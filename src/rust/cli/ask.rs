@@ -0,0 +1,126 @@
+use anyhow::{Context, Result};
+use std::io::Read;
+use std::time::Duration;
+
+use super::{CliArgs, EXIT_ERROR};
+use crate::popup::{show_popup, PopupError};
+use crate::types::{EnvContext, PopupRequest, Section};
+
+/// `ask` 的結束碼：確認
+pub const EXIT_CONFIRMED: i32 = 0;
+/// `ask` 的結束碼：取消或關閉視窗（參數錯誤或 UI 啟動失敗為 `EXIT_ERROR`）
+pub const EXIT_CANCELLED: i32 = 1;
+/// `ask` 的結束碼：逾時（與 coreutils `timeout` 相同）
+pub const EXIT_TIMEOUT: i32 = 124;
+
+pub async fn run(args: &CliArgs) -> Result<i32> {
+    if args.flag("help") {
+        print_usage();
+        return Ok(EXIT_CONFIRMED);
+    }
+
+    let request = match build_request(args) {
+        Ok(request) => request,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            print_usage();
+            return Ok(EXIT_ERROR);
+        }
+    };
+
+    let timeout = match args.value("timeout").map(str::parse::<u64>) {
+        Some(Ok(0)) | None => None,
+        Some(Ok(secs)) => Some(Duration::from_secs(secs)),
+        Some(Err(_)) => {
+            eprintln!("--timeout 必須是秒數");
+            return Ok(EXIT_ERROR);
+        }
+    };
+
+    match show_popup(&request, timeout).await {
+        Ok(response) => {
+            println!("{}", serde_json::to_string(&response)?);
+            Ok(if response.confirmed { EXIT_CONFIRMED } else { EXIT_CANCELLED })
+        }
+        Err(e) => match e.downcast_ref::<PopupError>() {
+            Some(PopupError::Timeout(_)) => {
+                eprintln!("{}", e);
+                Ok(EXIT_TIMEOUT)
+            }
+            None => {
+                eprintln!("UI 互動失敗: {:#}", e);
+                Ok(EXIT_ERROR)
+            }
+        },
+    }
+}
+
+fn build_request(args: &CliArgs) -> Result<PopupRequest> {
+    let message = args.value("message").context("缺少 --message")?;
+
+    // stdin 只能讀一次，第二個 `@-` 只會讀到空字串
+    let stdin_args = std::iter::once(message)
+        .chain(
+            args.values("section")
+                .into_iter()
+                .filter_map(|spec| spec.split_once('=').map(|(_, content)| content)),
+        )
+        .filter(|value| *value == "@-")
+        .count();
+    anyhow::ensure!(stdin_args <= 1, "只能有一個參數使用 @- 讀取 stdin");
+
+    let message = read_text_arg(message)?;
+
+    let sections = args
+        .values("section")
+        .into_iter()
+        .map(|spec| {
+            let (title, content) = spec
+                .split_once('=')
+                .with_context(|| format!("--section 格式應為 title=content: {}", spec))?;
+            Ok(Section {
+                title: title.to_string(),
+                content: read_text_arg(content)?,
+                selected: args.flag("select-all"),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(PopupRequest {
+        id: uuid::Uuid::new_v4().to_string(),
+        message,
+        sections,
+        is_markdown: !args.flag("plain"),
+        env_context: Some(EnvContext::detect()),
+    })
+}
+
+/// 解析文字參數：`@path` 讀取檔案，`@-` 讀取 stdin，其他視為字面內容
+fn read_text_arg(value: &str) -> Result<String> {
+    match value.strip_prefix('@') {
+        Some("-") => {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .context("讀取 stdin 失敗")?;
+            Ok(text)
+        }
+        Some(path) => std::fs::read_to_string(path)
+            .with_context(|| format!("讀取文件失敗: {}", path)),
+        None => Ok(value.to_string()),
+    }
+}
+
+fn print_usage() {
+    eprintln!(
+        "用法: claude-confirm ask --message <markdown|@file|@-> [選項]\n\n\
+        選項:\n  \
+          --section <title=content>  可選段落，可重複；content 也可用 @file\n  \
+          --select-all               段落預設為選中\n  \
+          --plain                    訊息不以 Markdown 渲染\n  \
+          --timeout <秒>             逾時自動關閉（0 表示不限）\n\n\
+        輸出: 用戶回應（UserResponse JSON）寫到 stdout\n\
+        結束碼: {} 確認 · {} 取消 · {} 逾時 · {} 錯誤",
+        EXIT_CONFIRMED, EXIT_CANCELLED, EXIT_TIMEOUT, EXIT_ERROR
+    );
+}
@@ -0,0 +1,126 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::CliArgs;
+use crate::config::Config;
+use crate::export::{self, ExportFormat};
+use crate::history;
+
+/// `export --list` 預設列出的筆數
+const DEFAULT_LIST_LIMIT: usize = 20;
+
+pub fn run(args: &CliArgs) -> Result<i32> {
+    if args.flag("help") {
+        print_usage();
+        return Ok(0);
+    }
+    if args.flag("list") {
+        return list(args);
+    }
+
+    let Some(id) = args.positional(0) else {
+        eprintln!("缺少歷史紀錄 ID");
+        print_usage();
+        return Ok(2);
+    };
+    let format = match args.value("format") {
+        None => ExportFormat::Md,
+        Some(name) => match ExportFormat::parse(name) {
+            Some(format) => format,
+            None => {
+                eprintln!("不支援的格式: {}（可用 md、html、pdf）", name);
+                return Ok(2);
+            }
+        },
+    };
+
+    match export_entry(id, format, args.value("output")) {
+        Ok(Some(path)) => {
+            println!("{}", path.display());
+            Ok(0)
+        }
+        Ok(None) => Ok(0),
+        Err(e) => {
+            eprintln!("匯出失敗: {:#}", e);
+            Ok(1)
+        }
+    }
+}
+
+/// 匯出一筆歷史紀錄，回傳寫入的路徑（輸出到 stdout 時為 None）
+///
+/// 未指定 `--output` 時寫入匯出目錄（不覆蓋既有檔案）；指定路徑時直接寫入該檔案。
+fn export_entry(id: &str, format: ExportFormat, output: Option<&str>) -> Result<Option<PathBuf>> {
+    let entry = history::load(id)?;
+    let config = Config::load().export;
+    let data = export::render(&entry, format, &config)?;
+
+    match output {
+        Some("-") => {
+            std::io::stdout().write_all(&data)?;
+            Ok(None)
+        }
+        Some(path) => {
+            let path = Path::new(path);
+            std::fs::write(path, &data)
+                .with_context(|| format!("寫入 {} 失敗", path.display()))?;
+            Ok(Some(path.to_path_buf()))
+        }
+        None => {
+            let filename = format!("{}.{}", export::file_stem(&entry), format.file_type());
+            export::save(&config, &filename, format.file_type(), &data).map(Some)
+        }
+    }
+}
+
+/// 列出最近的歷史紀錄：ID、回應時間、狀態、專案與訊息第一行
+fn list(args: &CliArgs) -> Result<i32> {
+    let limit = match args.value("limit").map(str::parse::<usize>) {
+        None => DEFAULT_LIST_LIMIT,
+        Some(Ok(limit)) => limit,
+        Some(Err(_)) => {
+            eprintln!("--limit 必須是數字");
+            return Ok(2);
+        }
+    };
+
+    for entry in history::list().into_iter().take(limit) {
+        let project = entry
+            .request
+            .env_context
+            .as_ref()
+            .and_then(|env| env.project_name.clone())
+            .unwrap_or_else(|| "-".to_string());
+        let summary: String = entry
+            .request
+            .message
+            .lines()
+            .map(|line| line.trim_start_matches('#').trim())
+            .find(|line| !line.is_empty())
+            .unwrap_or_default()
+            .chars()
+            .take(60)
+            .collect();
+        println!(
+            "{}  {}  {}  {}  {}",
+            entry.id,
+            entry.answered_at.format("%Y-%m-%d %H:%M"),
+            if entry.response.confirmed { "✅" } else { "❌" },
+            project,
+            summary
+        );
+    }
+    Ok(0)
+}
+
+fn print_usage() {
+    eprintln!(
+        "用法: claude-confirm export <歷史紀錄 ID> [選項]\n       \
+        claude-confirm export --list [--limit <n>]\n\n\
+        選項:\n  \
+          --format <md|html|pdf>  匯出格式（預設 md）\n  \
+          --output <path|->       寫入指定檔案，`-` 表示 stdout；未指定時寫入匯出目錄\n  \
+          --list                  列出最近的歷史紀錄（ID 可只輸入前綴）"
+    );
+}
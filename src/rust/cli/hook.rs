@@ -0,0 +1,265 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Read;
+use std::time::Duration;
+
+use super::CliArgs;
+use crate::config::{Config, HookConfig};
+use crate::git::GitInfo;
+use crate::popup::{show_tool_use_popup, PopupError};
+use crate::tool_use;
+use crate::types::{EnvContext, PopupRequest, Section, UserResponse};
+
+/// Claude Code 把結束碼 2 視為阻擋，stderr 的內容會作為原因回傳給 Claude
+const EXIT_BLOCK: i32 = 2;
+
+/// Claude Code 傳入的 PreToolUse hook 內容
+#[derive(Debug, Deserialize)]
+struct HookInput {
+    #[serde(default)]
+    hook_event_name: Option<String>,
+    #[serde(default)]
+    cwd: Option<String>,
+    tool_name: String,
+    #[serde(default)]
+    tool_input: Value,
+}
+
+/// 回傳給 Claude Code 的 hook 決定
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HookOutput {
+    hook_specific_output: PreToolUseDecision,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PreToolUseDecision {
+    hook_event_name: &'static str,
+    /// allow / deny / ask
+    permission_decision: &'static str,
+    permission_decision_reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated_input: Option<Value>,
+}
+
+impl PreToolUseDecision {
+    fn new(decision: &'static str, reason: impl Into<String>) -> Self {
+        Self {
+            hook_event_name: "PreToolUse",
+            permission_decision: decision,
+            permission_decision_reason: reason.into(),
+            updated_input: None,
+        }
+    }
+}
+
+pub async fn run(args: &CliArgs) -> Result<i32> {
+    // 結束碼 1 對 Claude Code 只是非阻擋的錯誤，工具會照常執行；無法判斷時一律阻擋
+    let input = match read_input() {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("claude-confirm hook 無法處理此工具調用，已阻擋: {:#}", e);
+            return Ok(EXIT_BLOCK);
+        }
+    };
+
+    // 只處理 PreToolUse，其他事件不輸出任何決定
+    if input.hook_event_name.as_deref().is_some_and(|name| name != "PreToolUse") {
+        return Ok(0);
+    }
+
+    let config = Config::load().hook;
+    if !args.flag("all") && !matches_rules(&config, &input) {
+        return Ok(0);
+    }
+
+    let timeout_secs = args
+        .value("timeout")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(config.timeout_secs);
+    let timeout = (timeout_secs > 0).then(|| Duration::from_secs(timeout_secs));
+
+    let decision = ask_user(&input, timeout).await;
+    let output = HookOutput {
+        hook_specific_output: decision,
+    };
+    println!("{}", serde_json::to_string(&output)?);
+
+    Ok(0)
+}
+
+fn read_input() -> Result<HookInput> {
+    let mut stdin = String::new();
+    std::io::stdin()
+        .read_to_string(&mut stdin)
+        .context("讀取 hook 輸入失敗")?;
+    serde_json::from_str(&stdin).context("解析 hook 輸入失敗")
+}
+
+/// 是否符合任一需要確認的規則
+fn matches_rules(config: &HookConfig, input: &HookInput) -> bool {
+    let primary = tool_use::primary_input(&input.tool_name, &input.tool_input)
+        .unwrap_or_else(|| input.tool_input.to_string());
+
+    config.rules.iter().any(|rule| {
+        let tool_matches = compile(&rule.tool).is_some_and(|re| re.is_match(&input.tool_name));
+        let input_matches = match &rule.input {
+            Some(pattern) => compile(pattern).is_some_and(|re| re.is_match(&primary)),
+            None => true,
+        };
+        tool_matches && input_matches
+    })
+}
+
+fn compile(pattern: &str) -> Option<Regex> {
+    Regex::new(pattern)
+        .inspect_err(|e| log::warn!("hook 規則不是有效的正則表達式 {:?}: {}", pattern, e))
+        .ok()
+}
+
+/// 顯示彈窗並把用戶回應轉為 hook 決定
+async fn ask_user(input: &HookInput, timeout: Option<Duration>) -> PreToolUseDecision {
+    let hook_context = EnvContext {
+        cwd: input.cwd.clone(),
+        git: input
+            .cwd
+            .as_deref()
+            .and_then(|cwd| GitInfo::detect_head(std::path::Path::new(cwd))),
+        project_name: input.cwd.as_deref().and_then(|cwd| {
+            std::path::Path::new(cwd)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(String::from)
+        }),
+        ..Default::default()
+    };
+
+    let request = PopupRequest {
+        id: uuid::Uuid::new_v4().to_string(),
+        message: tool_use::render_markdown(&input.tool_name, &input.tool_input),
+        sections: vec![Section {
+            title: "✏️ 修改後再執行".to_string(),
+            content: tool_use::edit_hint(&input.tool_name).to_string(),
+            selected: false,
+        }],
+        is_markdown: true,
+        env_context: Some(EnvContext::detect_quick().merge_with(Some(&hook_context))),
+    };
+
+    let response = match show_tool_use_popup(&request, timeout).await {
+        Ok(response) => response,
+        Err(e) => {
+            let reason = match e.downcast_ref::<PopupError>() {
+                Some(PopupError::Timeout(_)) => "claude-confirm 等待逾時，改由 Claude Code 詢問".to_string(),
+                None => format!("claude-confirm 無法顯示確認視窗: {}", e),
+            };
+            return PreToolUseDecision::new("ask", reason);
+        }
+    };
+
+    decide(input, &response)
+}
+
+/// 把用戶在彈窗中的回應轉為 hook 決定
+///
+/// allow 的決定無法把說明交給 Claude：確認時附上說明但沒有勾選修改，改為 deny 並帶上說明；
+/// 勾選修改卻沒有輸入內容時，不照原參數執行，改由 Claude Code 再詢問一次。
+fn decide(input: &HookInput, response: &UserResponse) -> PreToolUseDecision {
+    let note = response.user_input.trim();
+
+    if !response.confirmed {
+        let reason = if note.is_empty() {
+            "用戶在 claude-confirm 拒絕了此工具調用".to_string()
+        } else {
+            format!("用戶拒絕了此工具調用：{}", note)
+        };
+        return PreToolUseDecision::new("deny", reason);
+    }
+
+    let wants_edit = response.selected_sections.contains(&0);
+    match (wants_edit, note.is_empty()) {
+        (false, true) => PreToolUseDecision::new("allow", "用戶在 claude-confirm 允許了此工具調用"),
+        (false, false) => PreToolUseDecision::new(
+            "deny",
+            format!(
+                "用戶確認時附上了說明但沒有選擇修改參數，本次未執行，請依說明調整後重新調用：{}",
+                note
+            ),
+        ),
+        (true, true) => PreToolUseDecision::new(
+            "ask",
+            "用戶選擇了修改參數但沒有輸入內容，改由 Claude Code 詢問",
+        ),
+        (true, false) => match tool_use::edited_input(&input.tool_name, &input.tool_input, note) {
+            Ok(updated) => PreToolUseDecision {
+                updated_input: Some(updated),
+                ..PreToolUseDecision::new("allow", "用戶在 claude-confirm 修改參數後允許執行")
+            },
+            Err(e) => PreToolUseDecision::new("deny", format!("用戶修改的參數無效: {}", e)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn bash(command: &str) -> HookInput {
+        HookInput {
+            hook_event_name: Some("PreToolUse".to_string()),
+            cwd: None,
+            tool_name: "Bash".to_string(),
+            tool_input: json!({ "command": command }),
+        }
+    }
+
+    fn confirmed(selected_sections: Vec<usize>, user_input: &str) -> UserResponse {
+        UserResponse {
+            confirmed: true,
+            selected_sections,
+            user_input: user_input.to_string(),
+            ..UserResponse::cancelled()
+        }
+    }
+
+    #[test]
+    fn plain_confirm_allows() {
+        let decision = decide(&bash("rm -rf build"), &confirmed(vec![], ""));
+        assert_eq!(decision.permission_decision, "allow");
+        assert!(decision.updated_input.is_none());
+    }
+
+    #[test]
+    fn note_without_edit_denies_with_the_note() {
+        let decision = decide(&bash("rm -rf build"), &confirmed(vec![], "只刪 build/tmp"));
+        assert_eq!(decision.permission_decision, "deny");
+        assert!(decision
+            .permission_decision_reason
+            .contains("只刪 build/tmp"));
+        assert!(decision.updated_input.is_none());
+    }
+
+    #[test]
+    fn edit_without_note_does_not_run_the_original() {
+        let decision = decide(&bash("rm -rf build"), &confirmed(vec![0], "  \n"));
+        assert_eq!(decision.permission_decision, "ask");
+        assert!(decision.updated_input.is_none());
+    }
+
+    #[test]
+    fn edit_with_note_replaces_the_command() {
+        let decision = decide(
+            &bash("rm -rf build"),
+            &confirmed(vec![0], "rm -rf build/tmp"),
+        );
+        assert_eq!(decision.permission_decision, "allow");
+        assert_eq!(
+            decision.updated_input,
+            Some(json!({ "command": "rm -rf build/tmp" }))
+        );
+    }
+}
@@ -0,0 +1,601 @@
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::CliArgs;
+
+/// 預設註冊到 MCP 客戶端的服務名稱
+const DEFAULT_SERVER_NAME: &str = "claude-confirm";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Install,
+    Uninstall,
+}
+
+/// 已知的 MCP 客戶端設定檔
+struct ClientConfig {
+    id: &'static str,
+    name: &'static str,
+    path: PathBuf,
+}
+
+/// 列出目前平台上已知的 MCP 客戶端設定檔位置
+fn known_clients() -> Vec<ClientConfig> {
+    let mut clients = Vec::new();
+
+    if let Some(home) = dirs::home_dir() {
+        clients.push(ClientConfig {
+            id: "claude-code",
+            name: "Claude Code",
+            path: home.join(".claude.json"),
+        });
+        clients.push(ClientConfig {
+            id: "cursor",
+            name: "Cursor",
+            path: home.join(".cursor").join("mcp.json"),
+        });
+        clients.push(ClientConfig {
+            id: "windsurf",
+            name: "Windsurf",
+            path: home.join(".codeium").join("windsurf").join("mcp_config.json"),
+        });
+    }
+
+    // macOS: ~/Library/Application Support/Claude
+    // Linux: ~/.config/Claude
+    // Windows: %APPDATA%\Claude
+    if let Some(config_dir) = dirs::config_dir() {
+        clients.push(ClientConfig {
+            id: "claude-desktop",
+            name: "Claude Desktop",
+            path: config_dir.join("Claude").join("claude_desktop_config.json"),
+        });
+    }
+
+    clients
+}
+
+/// 設定檔存在，或客戶端目錄存在（已安裝但尚未建立設定檔）
+fn is_detected(client: &ClientConfig) -> bool {
+    client.path.exists()
+        || (client.id != "claude-code"
+            && client.path.parent().is_some_and(|dir| dir.is_dir()))
+}
+
+pub fn run(args: &CliArgs, action: Action) -> Result<i32> {
+    if args.flag("help") {
+        print_usage();
+        return Ok(0);
+    }
+
+    let dry_run = args.flag("dry-run");
+    let server_name = args.value("name").unwrap_or(DEFAULT_SERVER_NAME);
+
+    let targets: Vec<ClientConfig> = if let Some(path) = args.value("config") {
+        vec![ClientConfig {
+            id: "custom",
+            name: "自訂設定檔",
+            path: PathBuf::from(path),
+        }]
+    } else {
+        let clients = known_clients();
+        match args.value("client") {
+            Some(id) => {
+                let selected: Vec<_> = clients.into_iter().filter(|c| c.id == id).collect();
+                if selected.is_empty() {
+                    eprintln!("未知的客戶端: {}", id);
+                    print_usage();
+                    return Ok(2);
+                }
+                selected
+            }
+            None => clients.into_iter().filter(is_detected).collect(),
+        }
+    };
+
+    if targets.is_empty() {
+        println!("未偵測到任何 MCP 客戶端設定檔，可使用 --config <path> 指定");
+        return Ok(1);
+    }
+
+    let entry = server_entry()?;
+    let mut failed = false;
+
+    for client in &targets {
+        match apply(client, action, server_name, &entry, dry_run) {
+            Ok(message) => println!("[{}] {}", client.name, message),
+            Err(e) => {
+                eprintln!("[{}] 失敗: {:#}", client.name, e);
+                failed = true;
+            }
+        }
+    }
+
+    Ok(if failed { 1 } else { 0 })
+}
+
+/// 目前執行檔對應的 MCP 服務設定
+fn server_entry() -> Result<Value> {
+    let exe = std::env::current_exe().context("無法取得目前執行檔路徑")?;
+    let exe = exe.canonicalize().unwrap_or(exe);
+
+    Ok(serde_json::json!({
+        "command": exe.to_string_lossy(),
+        "args": [],
+    }))
+}
+
+/// 對單一設定檔插入或移除服務設定，回傳結果描述
+fn apply(
+    client: &ClientConfig,
+    action: Action,
+    server_name: &str,
+    entry: &Value,
+    dry_run: bool,
+) -> Result<String> {
+    let original = if client.path.exists() {
+        Some(
+            std::fs::read_to_string(&client.path)
+                .with_context(|| format!("讀取 {} 失敗", client.path.display()))?,
+        )
+    } else {
+        None
+    };
+
+    let mut config: Value = match original.as_deref() {
+        Some(text) if !text.trim().is_empty() => serde_json::from_str(text)
+            .with_context(|| format!("解析 {} 失敗", client.path.display()))?,
+        _ => Value::Object(Map::new()),
+    };
+
+    let root = config
+        .as_object_mut()
+        .context("設定檔頂層不是 JSON 物件")?;
+
+    let changed = match action {
+        Action::Install => {
+            let servers = root
+                .entry("mcpServers")
+                .or_insert_with(|| Value::Object(Map::new()))
+                .as_object_mut()
+                .context("mcpServers 不是 JSON 物件")?;
+
+            if servers.get(server_name) == Some(entry) {
+                false
+            } else {
+                servers.insert(server_name.to_string(), entry.clone());
+                true
+            }
+        }
+        Action::Uninstall => root
+            .get_mut("mcpServers")
+            .and_then(Value::as_object_mut)
+            .is_some_and(|servers| servers.remove(server_name).is_some()),
+    };
+
+    let path = client.path.display();
+    if !changed {
+        return Ok(match action {
+            Action::Install => format!("已是最新設定，無需變更 ({})", path),
+            Action::Uninstall => format!("未註冊 {}，無需變更 ({})", server_name, path),
+        });
+    }
+
+    let updated = serde_json::to_string_pretty(&config)? + "\n";
+
+    if dry_run {
+        let before = original
+            .as_deref()
+            .map(pretty_or_raw)
+            .unwrap_or_default();
+        println!("--- {}", path);
+        println!("+++ {} (預覽)", path);
+        print!("{}", line_diff(&before, &updated));
+        if original.as_deref().is_some_and(|text| text != before) {
+            println!(
+                "注意：寫入時整個檔案會以 2 格縮排的 JSON 重新輸出，原檔的排版不會保留\n\
+                （上方差異已忽略排版的不同）"
+            );
+        }
+        return Ok("--dry-run：未寫入任何變更".to_string());
+    }
+
+    let backup = match original {
+        Some(_) => Some(backup_file(&client.path)?),
+        None => {
+            if let Some(dir) = client.path.parent() {
+                std::fs::create_dir_all(dir)
+                    .with_context(|| format!("建立目錄 {} 失敗", dir.display()))?;
+            }
+            None
+        }
+    };
+
+    write_atomic(&client.path, &updated)?;
+
+    let verb = match action {
+        Action::Install => "已註冊",
+        Action::Uninstall => "已移除",
+    };
+    Ok(match backup {
+        Some(backup) => format!("{} {} ({}，備份: {})", verb, server_name, path, backup.display()),
+        None => format!("{} {} ({})", verb, server_name, path),
+    })
+}
+
+/// 以相同的格式重新輸出原始 JSON，讓 diff 只顯示實際變更
+fn pretty_or_raw(text: &str) -> String {
+    serde_json::from_str::<Value>(text)
+        .ok()
+        .and_then(|v| serde_json::to_string_pretty(&v).ok())
+        .map(|s| s + "\n")
+        .unwrap_or_else(|| text.to_string())
+}
+
+/// 同一秒內最多嘗試的備份後綴數
+const MAX_BACKUP_SUFFIX: usize = 1000;
+
+/// 在原檔旁建立帶時間戳的備份
+///
+/// 時間戳只精確到秒，同一秒內重複執行時改用 `.bak-<時間>-1`、`-2`…；
+/// 備份以 `create_new` 建立，絕不覆寫既有的備份，權限與原檔相同。
+fn backup_file(path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let content = std::fs::read(path).with_context(|| format!("備份 {} 失敗", path.display()))?;
+    let permissions = std::fs::metadata(path).ok().map(|m| m.permissions());
+
+    for suffix in 0..MAX_BACKUP_SUFFIX {
+        let backup = match suffix {
+            0 => path.with_file_name(format!("{}.bak-{}", file_name, stamp)),
+            n => path.with_file_name(format!("{}.bak-{}-{}", file_name, stamp, n)),
+        };
+        let file = match private_options().create_new(true).open(&backup) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => {
+                return Err(e).with_context(|| format!("建立備份 {} 失敗", backup.display()))
+            }
+        };
+
+        let result = write_all_synced(file, &content)
+            .and_then(|()| match &permissions {
+                Some(permissions) => std::fs::set_permissions(&backup, permissions.clone()),
+                None => Ok(()),
+            })
+            .with_context(|| format!("寫入備份 {} 失敗", backup.display()));
+        if result.is_err() {
+            let _ = std::fs::remove_file(&backup);
+        }
+        return result.map(|()| backup);
+    }
+
+    anyhow::bail!("{} 的備份名稱都已被使用", path.display())
+}
+
+/// 先寫入暫存檔再重新命名，避免寫到一半損壞設定檔
+///
+/// 暫存檔沿用原檔的權限：`~/.claude.json` 通常是 0600 且含有帳號資訊，
+/// 不能在取代後變成依 umask 建立的 0644。Unix 上暫存檔先以 0600 建立，寫入後才套用原檔的權限。
+fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let tmp = path.with_extension(format!("tmp-{}", std::process::id()));
+    let permissions = std::fs::metadata(path).ok().map(|m| m.permissions());
+
+    let result = write_private(&tmp, content)
+        .with_context(|| format!("寫入 {} 失敗", tmp.display()))
+        .and_then(|()| match permissions {
+            Some(permissions) => std::fs::set_permissions(&tmp, permissions)
+                .with_context(|| format!("設定 {} 的權限失敗", tmp.display())),
+            None => Ok(()),
+        })
+        .and_then(|()| {
+            std::fs::rename(&tmp, path).with_context(|| format!("取代 {} 失敗", path.display()))
+        });
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// 建立只有擁有者可讀寫的檔案並寫入內容
+fn write_private(path: &Path, content: &str) -> std::io::Result<()> {
+    let file = private_options().create(true).truncate(true).open(path)?;
+    write_all_synced(file, content.as_bytes())
+}
+
+/// 寫入用的開檔選項，Unix 上新檔案只有擁有者可讀寫
+fn private_options() -> std::fs::OpenOptions {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+}
+
+/// 寫入全部內容並同步到磁碟
+fn write_all_synced(mut file: std::fs::File, content: &[u8]) -> std::io::Result<()> {
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+/// 差異前後保留的上下文行數
+const DIFF_CONTEXT: usize = 3;
+
+/// 逐行比較，輸出 unified diff（`@@ -起始,行數 +起始,行數 @@`）
+///
+/// 設定檔（尤其是 `~/.claude.json`）可能很大，先去除相同的開頭與結尾，
+/// 只對中間變動的區塊做 LCS；相距超過兩倍上下文的變更分成不同的 hunk。
+fn line_diff(before: &str, after: &str) -> String {
+    let a: Vec<&str> = before.lines().collect();
+    let b: Vec<&str> = after.lines().collect();
+
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+
+    let mut lcs = vec![vec![0usize; b_mid.len() + 1]; a_mid.len() + 1];
+    for i in (0..a_mid.len()).rev() {
+        for j in (0..b_mid.len()).rev() {
+            lcs[i][j] = if a_mid[i] == b_mid[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // 整個檔案的編輯序列：(' ' | '-' | '+', 行)
+    let mut ops: Vec<(char, &str)> = a[..prefix].iter().map(|line| (' ', *line)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a_mid.len() || j < b_mid.len() {
+        if i < a_mid.len() && j < b_mid.len() && a_mid[i] == b_mid[j] {
+            ops.push((' ', a_mid[i]));
+            i += 1;
+            j += 1;
+        } else if i < a_mid.len() && (j == b_mid.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', a_mid[i]));
+            i += 1;
+        } else {
+            ops.push(('+', b_mid[j]));
+            j += 1;
+        }
+    }
+    ops.extend(a[a.len() - suffix..].iter().map(|line| (' ', *line)));
+
+    // 每個 op 之前已經過的原檔與新檔行數
+    let mut positions = Vec::with_capacity(ops.len());
+    let (mut old_line, mut new_line) = (0, 0);
+    for (kind, _) in &ops {
+        positions.push((old_line, new_line));
+        match kind {
+            '-' => old_line += 1,
+            '+' => new_line += 1,
+            _ => {
+                old_line += 1;
+                new_line += 1;
+            }
+        }
+    }
+
+    let changes: Vec<usize> = (0..ops.len()).filter(|&k| ops[k].0 != ' ').collect();
+    let mut out = String::new();
+    let mut k = 0;
+    while k < changes.len() {
+        let first = changes[k];
+        let mut last = first;
+        while k + 1 < changes.len() && changes[k + 1] - last <= 2 * DIFF_CONTEXT + 1 {
+            k += 1;
+            last = changes[k];
+        }
+        k += 1;
+
+        let start = first.saturating_sub(DIFF_CONTEXT);
+        let end = (last + DIFF_CONTEXT + 1).min(ops.len());
+        let hunk = &ops[start..end];
+        let old_len = hunk.iter().filter(|(kind, _)| *kind != '+').count();
+        let new_len = hunk.iter().filter(|(kind, _)| *kind != '-').count();
+        let (old_start, new_start) = positions[start];
+
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_len),
+            hunk_range(new_start, new_len)
+        ));
+        for (kind, line) in hunk {
+            out.push_str(&format!("{}{}\n", kind, line));
+        }
+    }
+
+    out
+}
+
+/// hunk 標頭的範圍；空範圍的起始行是範圍前一行（GNU diff 的格式）
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+fn print_usage() {
+    println!(
+        "用法: claude-confirm install|uninstall [選項]\n\n\
+        選項:\n  \
+          --client <id>     只處理指定客戶端（claude-code、claude-desktop、cursor、windsurf）\n  \
+          --config <path>   指定任意 JSON 設定檔（需使用 mcpServers 格式）\n  \
+          --name <name>     服務名稱（預設 {}）\n  \
+          --dry-run         只顯示差異，不寫入檔案\n\n\
+        未指定 --client 時，會處理所有偵測到的客戶端。修改前會在原檔旁建立 .bak-<時間> 備份，\n\
+        寫入時整個檔案會以 2 格縮排的 JSON 重新輸出（保留鍵的順序與檔案權限）。",
+        DEFAULT_SERVER_NAME
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// 在暫存目錄中建立設定檔，結束時刪除整個目錄
+    struct TempConfig {
+        dir: PathBuf,
+        client: ClientConfig,
+    }
+
+    impl TempConfig {
+        fn new(content: &str) -> Self {
+            let dir = std::env::temp_dir()
+                .join(format!("claude-confirm-install-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let path = dir.join("config.json");
+            std::fs::write(&path, content).unwrap();
+            Self {
+                dir,
+                client: ClientConfig {
+                    id: "custom",
+                    name: "test",
+                    path,
+                },
+            }
+        }
+
+        fn apply(&self, action: Action) -> String {
+            let entry = json!({ "command": "/usr/local/bin/claude-confirm", "args": [] });
+            apply(&self.client, action, DEFAULT_SERVER_NAME, &entry, false).unwrap()
+        }
+
+        fn read(&self) -> Value {
+            serde_json::from_str(&std::fs::read_to_string(&self.client.path).unwrap()).unwrap()
+        }
+    }
+
+    impl Drop for TempConfig {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    const ORIGINAL: &str = r#"{
+  "oauthAccount": { "email": "user@example.com" },
+  "mcpServers": { "other": { "command": "other-server" } },
+  "projects": { "/repo": { "allowedTools": [] } }
+}"#;
+
+    #[test]
+    fn install_and_uninstall_are_idempotent() {
+        let config = TempConfig::new(ORIGINAL);
+
+        assert!(config.apply(Action::Install).starts_with("已註冊"));
+        let installed = config.read();
+        assert!(config.apply(Action::Install).starts_with("已是最新設定"));
+        assert_eq!(config.read(), installed);
+
+        assert!(config.apply(Action::Uninstall).starts_with("已移除"));
+        let uninstalled = config.read();
+        assert!(config.apply(Action::Uninstall).starts_with("未註冊"));
+        assert_eq!(config.read(), uninstalled);
+
+        let original: Value = serde_json::from_str(ORIGINAL).unwrap();
+        assert_eq!(uninstalled, original);
+    }
+
+    #[test]
+    fn install_keeps_unrelated_keys() {
+        let config = TempConfig::new(ORIGINAL);
+        config.apply(Action::Install);
+
+        let installed = config.read();
+        assert_eq!(installed["oauthAccount"]["email"], "user@example.com");
+        assert_eq!(installed["projects"]["/repo"]["allowedTools"], json!([]));
+        assert_eq!(installed["mcpServers"]["other"]["command"], "other-server");
+        assert_eq!(
+            installed["mcpServers"][DEFAULT_SERVER_NAME]["command"],
+            "/usr/local/bin/claude-confirm"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn install_keeps_file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let config = TempConfig::new(ORIGINAL);
+        std::fs::set_permissions(&config.client.path, std::fs::Permissions::from_mode(0o600))
+            .unwrap();
+        config.apply(Action::Install);
+
+        let mode = std::fs::metadata(&config.client.path)
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn backups_in_the_same_second_get_a_suffix() {
+        let config = TempConfig::new(ORIGINAL);
+
+        let first = backup_file(&config.client.path).unwrap();
+        std::fs::write(&config.client.path, "{}").unwrap();
+        let second = backup_file(&config.client.path).unwrap();
+        let third = backup_file(&config.client.path).unwrap();
+
+        assert_ne!(first, second);
+        assert_ne!(second, third);
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), ORIGINAL);
+        assert_eq!(std::fs::read_to_string(&second).unwrap(), "{}");
+        assert_eq!(std::fs::read_to_string(&third).unwrap(), "{}");
+    }
+
+    #[test]
+    fn line_diff_emits_hunk_headers_with_counts() {
+        let before = "a\nb\nc\nd\ne\n";
+        let after = "a\nb\nc\nX\nd\ne\n";
+        assert_eq!(
+            line_diff(before, after),
+            "@@ -1,5 +1,6 @@\n a\n b\n c\n+X\n d\n e\n"
+        );
+    }
+
+    #[test]
+    fn line_diff_splits_distant_changes_into_hunks() {
+        let lines = |replace: &dyn Fn(u32) -> String| -> String {
+            (1..=20).map(|n| replace(n) + "\n").collect()
+        };
+        let before = lines(&|n| n.to_string());
+        let after = lines(&|n| match n {
+            2 => "two".to_string(),
+            18 => "eighteen".to_string(),
+            _ => n.to_string(),
+        });
+        let diff = line_diff(&before, &after);
+
+        let headers: Vec<&str> = diff.lines().filter(|line| line.starts_with("@@")).collect();
+        assert_eq!(headers, ["@@ -1,5 +1,5 @@", "@@ -15,6 +15,6 @@"]);
+        assert!(diff.contains("-2\n+two\n"));
+        assert!(diff.contains("-18\n+eighteen\n"));
+    }
+
+    #[test]
+    fn line_diff_of_new_file() {
+        assert_eq!(line_diff("", "{\n}\n"), "@@ -0,0 +1,2 @@\n+{\n+}\n");
+        assert_eq!(line_diff("same\n", "same\n"), "");
+    }
+}
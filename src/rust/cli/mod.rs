@@ -0,0 +1,145 @@
+mod ask;
+mod export;
+mod hook;
+mod install;
+mod self_update;
+
+use anyhow::Result;
+
+/// 子命令執行失敗的結束碼，與 `ask` 的取消（1）區分
+pub const EXIT_ERROR: i32 = 2;
+
+/// 執行 `claude-confirm` 的子命令
+///
+/// 回傳 `None` 代表不是已知的子命令，應照常啟動 MCP 服務器。
+pub async fn run(args: &[String]) -> Result<Option<i32>> {
+    let Some((command, rest)) = args.split_first() else {
+        return Ok(None);
+    };
+
+    let args = CliArgs::parse(rest, value_options(command));
+    let code = match command.as_str() {
+        "ask" => ask::run(&args).await?,
+        "hook" => hook::run(&args).await?,
+        "export" => export::run(&args)?,
+        "install" => install::run(&args, install::Action::Install)?,
+        "uninstall" => install::run(&args, install::Action::Uninstall)?,
+        "self-update" => self_update::run(&args).await?,
+        _ => return Ok(None),
+    };
+
+    Ok(Some(code))
+}
+
+/// 各子命令需要值的選項，其餘 `--xxx` 都是旗標
+fn value_options(command: &str) -> &'static [&'static str] {
+    match command {
+        "ask" => &["message", "section", "timeout"],
+        "export" => &["format", "output", "limit"],
+        "install" | "uninstall" => &["client", "config", "name"],
+        _ => &[],
+    }
+}
+
+/// 簡易的命令列參數解析（`--flag`、`--key value`、`--key=value` 與位置參數）
+#[derive(Debug, Default)]
+pub(crate) struct CliArgs {
+    options: Vec<(String, Option<String>)>,
+    positionals: Vec<String>,
+}
+
+impl CliArgs {
+    /// `value_options` 中的選項一定取下一個參數為值（即使以 `--` 開頭，例如 `--message "--- notes"`），
+    /// 其他選項都是旗標，不會吃掉後面的位置參數
+    pub(crate) fn parse(args: &[String], value_options: &[&str]) -> Self {
+        let mut parsed = Self::default();
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                parsed.positionals.push(arg.clone());
+                continue;
+            };
+
+            if let Some((key, value)) = name.split_once('=') {
+                parsed.options.push((key.to_string(), Some(value.to_string())));
+            } else if value_options.contains(&name) {
+                parsed.options.push((name.to_string(), iter.next().cloned()));
+            } else {
+                parsed.options.push((name.to_string(), None));
+            }
+        }
+
+        parsed
+    }
+
+    /// 是否帶有指定旗標
+    pub(crate) fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(key, _)| key == name)
+    }
+
+    /// 取得指定參數的最後一個值
+    pub(crate) fn value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(key, _)| key == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    /// 取得第 `index` 個位置參數（不以 `--` 開頭、也不是選項值的參數）
+    pub(crate) fn positional(&self, index: usize) -> Option<&str> {
+        self.positionals.get(index).map(String::as_str)
+    }
+
+    /// 取得指定參數的所有值（可重複傳入）
+    pub(crate) fn values(&self, name: &str) -> Vec<&str> {
+        self.options
+            .iter()
+            .filter(|(key, _)| key == name)
+            .filter_map(|(_, value)| value.as_deref())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str], value_options: &[&str]) -> CliArgs {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        CliArgs::parse(&args, value_options)
+    }
+
+    #[test]
+    fn value_options_take_next_argument_even_if_it_looks_like_a_flag() {
+        let args = parse(&["--message", "--- notes", "--plain"], &["message"]);
+        assert_eq!(args.value("message"), Some("--- notes"));
+        assert!(args.flag("plain"));
+    }
+
+    #[test]
+    fn flags_do_not_consume_positionals() {
+        let args = parse(&["--list", "abc123", "--format", "pdf"], &["format"]);
+        assert!(args.flag("list"));
+        assert_eq!(args.positional(0), Some("abc123"));
+        assert_eq!(args.value("format"), Some("pdf"));
+    }
+
+    #[test]
+    fn equals_form_and_repeated_values() {
+        let args = parse(
+            &["--section=A=one", "--section", "B=two", "--name=x", "--name", "y"],
+            &["section", "name"],
+        );
+        assert_eq!(args.values("section"), ["A=one", "B=two"]);
+        assert_eq!(args.value("name"), Some("y"));
+    }
+
+    #[test]
+    fn missing_value_at_end() {
+        let args = parse(&["--message"], &["message"]);
+        assert!(args.flag("message"));
+        assert_eq!(args.value("message"), None);
+    }
+}
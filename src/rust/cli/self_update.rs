@@ -0,0 +1,84 @@
+use anyhow::Result;
+
+use super::CliArgs;
+use crate::config::Config;
+use crate::update;
+
+pub async fn run(args: &CliArgs) -> Result<i32> {
+    if args.flag("help") {
+        print_usage();
+        return Ok(0);
+    }
+
+    if args.flag("rollback") {
+        return match update::rollback() {
+            Ok(manifest) => {
+                for file in &manifest.files {
+                    println!("已還原: {}", file.target.display());
+                }
+                println!("已從 {} 還原到 {}", manifest.replaced_by, manifest.version);
+                Ok(0)
+            }
+            Err(e) => {
+                eprintln!("還原失敗: {:#}", e);
+                Ok(1)
+            }
+        };
+    }
+
+    let config = Config::load().update;
+
+    if args.flag("check") {
+        return match update::check(&config).await {
+            Ok(check) => {
+                match &check.latest {
+                    Some((version, _)) => {
+                        println!("目前版本 {}，最新版本 {}", check.current, version)
+                    }
+                    None => println!("目前版本 {}，更新頻道沒有可用的 release", check.current),
+                }
+                // 與 `test`/`grep` 相同：有更新時結束碼為 0
+                Ok(if check.has_update() { 0 } else { 1 })
+            }
+            Err(e) => {
+                eprintln!("檢查更新失敗: {:#}", e);
+                Ok(2)
+            }
+        };
+    }
+
+    println!("正在檢查更新…");
+    match update::self_update(&config, args.flag("force")).await {
+        Ok(Some(outcome)) => {
+            for path in &outcome.replaced {
+                println!("已更新: {}", path.display());
+            }
+            println!(
+                "已從 {} 更新到 {}（可用 `claude-confirm self-update --rollback` 還原）",
+                outcome.from, outcome.to
+            );
+            println!("執行中的 UI host 需重新啟動（從托盤選單結束）才會使用新版本");
+            Ok(0)
+        }
+        Ok(None) => {
+            println!("已是最新版本 {}", update::current_version());
+            Ok(0)
+        }
+        Err(e) => {
+            eprintln!("更新失敗: {:#}", e);
+            Ok(1)
+        }
+    }
+}
+
+fn print_usage() {
+    eprintln!(
+        "用法: claude-confirm self-update [選項]\n\n\
+        下載此平台的 release，驗證 SHA-256 與 minisign 簽章後替換 claude-confirm 與 claude-confirm-ui\n\
+        （只接受 https；兩個執行檔都必須找到，位於 .app bundle 內時請改為下載新版的 claude-confirm.app）\n\n\
+        選項:\n  \
+          --check     只檢查是否有新版本（有更新時結束碼為 0，沒有為 1）\n  \
+          --force     即使已是最新版本也重新安裝\n  \
+          --rollback  還原到上一次更新前的版本"
+    );
+}
@@ -0,0 +1,281 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::export::ExportFormat;
+use crate::update::UpdateChannel;
+
+/// 指定設定檔路徑的環境變數
+const CONFIG_ENV: &str = "CLAUDE_CONFIRM_CONFIG";
+
+/// 用戶設定（`~/.config/claude-confirm/config.json`）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// PreToolUse hook 設定
+    pub hook: HookConfig,
+    /// 回應後返回原終端機
+    pub refocus: RefocusConfig,
+    /// 依 MCP 客戶端覆蓋 instructions 與工具描述
+    pub clients: Vec<ClientProfile>,
+    /// UI 程序設定
+    pub ui: UiConfig,
+    /// 歷史紀錄
+    pub history: HistoryConfig,
+    /// @ 檔案選擇器的掃描設定
+    pub files: FilesConfig,
+    /// 附件大小與數量上限
+    pub attachments: AttachmentsConfig,
+    /// 匯出設定
+    pub export: ExportConfig,
+    /// 把每次互動記錄到專案內的 `.claude-confirm/sessions/`
+    pub sessions: SessionsConfig,
+    /// 版本檢查
+    pub update: UpdateConfig,
+}
+
+impl Config {
+    /// 設定檔路徑，可用 `CLAUDE_CONFIRM_CONFIG` 覆蓋
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os(CONFIG_ENV) {
+            return Some(PathBuf::from(path));
+        }
+        dirs::config_dir().map(|dir| dir.join("claude-confirm").join("config.json"))
+    }
+
+    /// 載入設定，檔案不存在或格式錯誤時使用預設值
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                log::warn!("解析設定檔 {:?} 失敗，使用預設值: {}", path, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// 找到符合客戶端名稱的設定（第一個符合者優先）
+    pub fn client_profile(&self, client_name: &str) -> Option<&ClientProfile> {
+        let client_name = client_name.to_lowercase();
+        self.clients
+            .iter()
+            .find(|profile| client_name.contains(&profile.client.to_lowercase()))
+    }
+}
+
+/// PreToolUse hook 設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HookConfig {
+    /// 符合任一規則的工具調用才會彈窗確認，其餘直接放行給 Claude Code 處理
+    pub rules: Vec<HookRule>,
+    /// 等待用戶回應的秒數，逾時交回 Claude Code 自己的權限提示（0 表示不限）
+    pub timeout_secs: u64,
+}
+
+impl Default for HookConfig {
+    fn default() -> Self {
+        Self {
+            rules: vec![
+                HookRule::new(
+                    "^Bash$",
+                    r"\brm\s+-[a-zA-Z]*[rf]|\bsudo\b|\bgit\s+push\b.*(--force|\s-f\b)|\bgit\s+reset\s+--hard\b|\bgit\s+clean\s+-[a-zA-Z]*f|\bmkfs\b|\bdd\s+if=|\bchmod\s+-R\b|\bchown\s+-R\b|(curl|wget)\b.*\|\s*(ba|z)?sh\b|(?i:\bdrop\s+(table|database)\b)",
+                ),
+            ],
+            timeout_secs: 55,
+        }
+    }
+}
+
+/// hook 比對規則（正則表達式）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookRule {
+    /// 比對工具名稱，例如 `^Bash$`、`^(Write|Edit)$`
+    pub tool: String,
+    /// 比對工具的主要輸入（Bash 指令、檔案路徑、URL），未設定時符合所有輸入
+    #[serde(default)]
+    pub input: Option<String>,
+}
+
+impl HookRule {
+    fn new(tool: &str, input: &str) -> Self {
+        Self {
+            tool: tool.to_string(),
+            input: Some(input.to_string()),
+        }
+    }
+}
+
+/// 回應後把焦點還給發出請求的終端機
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RefocusConfig {
+    /// 總開關，預設關閉
+    pub enabled: bool,
+    /// 在 tmux 內時切換到原本的 window/pane
+    pub tmux: bool,
+    /// X11 下依進程 ID 找到終端機視窗並啟用（需要 xdotool 或 wmctrl）
+    pub x11: bool,
+}
+
+impl Default for RefocusConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tmux: true,
+            x11: true,
+        }
+    }
+}
+
+/// 針對特定 MCP 客戶端的設定
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientProfile {
+    /// 比對 initialize 傳入的客戶端名稱（不分大小寫，包含即符合）
+    pub client: String,
+    /// 取代預設的 instructions
+    pub instructions: Option<String>,
+    /// 取代預設的工具描述，key 為工具名稱（`confirm`、`approve_tool_use`）
+    pub tool_descriptions: HashMap<String, String>,
+}
+
+/// UI 程序設定
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    /// 使用常駐的 UI host（托盤 + 單一視窗處理所有請求），預設關閉，每個請求各開一個視窗
+    pub host: bool,
+}
+
+/// 歷史紀錄（`<data_local_dir>/claude-confirm/history`）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// 總開關；關閉時也不會自動封存（`export.archive`）或寫入專案互動紀錄（`sessions`）
+    pub enabled: bool,
+    /// 也記錄工具調用確認（hook 與 `approve_tool_use`），內容包含指令與檔案內容，預設不記錄
+    pub tool_calls: bool,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            tool_calls: false,
+        }
+    }
+}
+
+/// @ 檔案選擇器的掃描設定
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilesConfig {
+    /// 掃描的最大目錄深度
+    pub max_depth: usize,
+    /// 最多列出的項目數，超過時停止掃描
+    pub max_entries: usize,
+    /// 是否列出隱藏檔（以 `.` 開頭）
+    pub hidden: bool,
+}
+
+impl Default for FilesConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: 12,
+            max_entries: 20_000,
+            hidden: false,
+        }
+    }
+}
+
+/// 匯出設定
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    /// 匯出目錄（可用 `~/` 開頭），未設定時為系統的 Downloads 目錄
+    pub dir: Option<PathBuf>,
+    /// 每次回應後自動封存的格式（`md`、`html`、`pdf`），空陣列表示不封存
+    pub archive: Vec<ExportFormat>,
+    /// 自動封存的目錄，未設定時為 `<data_local_dir>/claude-confirm/archive`
+    pub archive_dir: Option<PathBuf>,
+    /// PDF 使用的 TrueType 字型檔（`.ttf`），未設定時在系統字型中尋找支援中文的字型
+    pub pdf_font: Option<PathBuf>,
+}
+
+/// 專案內的互動紀錄（`<cwd>/.claude-confirm/sessions/<時間>-<id>.md`）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionsConfig {
+    /// 總開關，預設關閉
+    pub enabled: bool,
+    /// 每個專案保留的紀錄數上限，超過時刪除最舊的（0 表示不限）
+    pub max_files: usize,
+    /// 每個專案紀錄的總大小上限（bytes），超過時刪除最舊的（0 表示不限）
+    pub max_total_bytes: u64,
+    /// 紀錄中內嵌圖片（base64）；預設只列出圖片名稱，避免紀錄檔過大
+    pub embed_images: bool,
+    /// 讓 git 追蹤紀錄（在 `.claude-confirm/.gitignore` 加上例外），預設與其他專案資料一樣忽略
+    pub commit: bool,
+    /// 紀錄中包含本機環境（工作目錄、主機與用戶、SSH 來源 IP、程序鏈等），預設只記錄客戶端、專案與 git；
+    /// `commit` 開啟時一律不包含
+    pub env: bool,
+}
+
+impl Default for SessionsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_files: 200,
+            max_total_bytes: 20 * 1024 * 1024,
+            embed_images: false,
+            commit: false,
+            env: false,
+        }
+    }
+}
+
+/// 版本檢查與自動更新設定
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateConfig {
+    /// `stable` 只看正式版，`prerelease` 也包括預覽版
+    pub channel: UpdateChannel,
+    /// release 列表的網址（GitHub releases API 格式，只接受 https），可指向內部鏡像；未設定時使用 GitHub
+    pub endpoint: Option<String>,
+    /// `self-update` 下載的 asset 名稱，可用 `{version}`、`{os}`、`{arch}`，
+    /// 未設定時為 `claude-confirm-{version}-{os}-{arch}.tar.gz`
+    pub asset: Option<String>,
+    /// 驗證 `.minisig` 簽章的 minisign 公鑰，取代編譯時內建的發布公鑰（例如內部鏡像）
+    pub public_key: Option<String>,
+}
+
+/// 附件設定（每個請求各自計算）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttachmentsConfig {
+    /// 單一附件內容的上限（bytes），超過時文字檔截斷、其他檔案拒絕
+    pub max_file_bytes: u64,
+    /// 一個請求附上的內容總量上限（bytes），專案內只回傳路徑的檔案不計入
+    pub max_total_bytes: u64,
+    /// 一個請求最多的附件數
+    pub max_count: usize,
+    /// 送出時把附件（包括貼上的圖片）保存到 `<cwd>/.claude-confirm/attachments/<request-id>/`，
+    /// 只回傳檔案路徑而不附上內容
+    pub persist: bool,
+}
+
+impl Default for AttachmentsConfig {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: 1024 * 1024,
+            max_total_bytes: 8 * 1024 * 1024,
+            max_count: 20,
+            persist: false,
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag};
+
+/// 匯出 HTML 的樣式（內嵌，單一檔案即可開啟；跟隨系統深色模式）
+const STYLE: &str = r#"
+:root { color-scheme: light dark; --fg: #333; --muted: #666; --bg: #fff; --code-bg: #f5f5f5; --border: #e0e0e0; --accent: #9333ea; }
+@media (prefers-color-scheme: dark) {
+  :root { --fg: #e5e5e5; --muted: #a3a3a3; --bg: #1a1a1a; --code-bg: #262626; --border: #404040; --accent: #c084fc; }
+}
+body { max-width: 860px; margin: 2rem auto; padding: 0 1.5rem; background: var(--bg); color: var(--fg);
+  font: 15px/1.65 -apple-system, BlinkMacSystemFont, "Segoe UI", "PingFang TC", "Noto Sans CJK TC", "Microsoft JhengHei", sans-serif; }
+h1, h2, h3 { line-height: 1.3; }
+h2 { border-bottom: 1px solid var(--border); padding-bottom: 0.3rem; }
+a { color: var(--accent); }
+hr { border: none; border-top: 1px solid var(--border); margin: 2rem 0; }
+code { font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: 0.9em; background: var(--code-bg); padding: 0.1em 0.3em; border-radius: 4px; }
+pre { background: var(--code-bg); padding: 0.75rem 1rem; border-radius: 6px; overflow-x: auto; }
+pre code { background: none; padding: 0; }
+blockquote { margin: 0; padding-left: 1rem; border-left: 3px solid var(--border); color: var(--muted); }
+table { border-collapse: collapse; }
+th, td { border: 1px solid var(--border); padding: 0.3rem 0.6rem; }
+img { max-width: 100%; }
+"#;
+
+/// 把 Markdown 轉為獨立的 HTML 頁面
+///
+/// 訊息與用戶輸入中的原始 HTML 一律當作文字顯示，連結與圖片只保留 http、https、mailto 與相對網址，
+/// 避免開啟匯出檔時執行其中的腳本（例如 `[x](javascript:…)`）。圖片另外允許內嵌的
+/// PNG、JPEG、GIF、WebP `data:` 網址（不含可執行腳本的 SVG）。
+pub(super) fn render(title: &str, markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let parser = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: safe_url(dest_url, false),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: safe_url(dest_url, true),
+            title,
+            id,
+        }),
+        event => event,
+    });
+
+    let mut body = String::new();
+    pulldown_cmark::html::push_html(&mut body, parser);
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+/// 圖片可內嵌的 `data:` 類型
+const DATA_IMAGE_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+/// 不允許的網址（`javascript:`、`vbscript:`、連結或 SVG 的 `data:` 等）改為空字串
+fn safe_url(url: CowStr<'_>, image: bool) -> CowStr<'_> {
+    if is_allowed_url(&url, image) {
+        url
+    } else {
+        CowStr::Borrowed("")
+    }
+}
+
+fn is_allowed_url(url: &str, image: bool) -> bool {
+    // 瀏覽器解析網址時會忽略其中的空白與控制字元（例如 `java\tscript:`）
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect();
+    let Some((scheme, rest)) = url.split_once(':') else {
+        return true;
+    };
+    // 冒號在 `/`、`?`、`#` 之後時是相對網址的一部分（例如 `docs/a:b.md`）
+    if scheme.contains(['/', '?', '#']) {
+        return true;
+    }
+    if scheme.eq_ignore_ascii_case("data") {
+        return image && is_data_image(rest);
+    }
+    ["http", "https", "mailto"]
+        .iter()
+        .any(|allowed| scheme.eq_ignore_ascii_case(allowed))
+}
+
+/// `data:` 之後是否為 `<點陣圖類型>;base64,<內容>`
+fn is_data_image(rest: &str) -> bool {
+    let Some((header, _)) = rest.split_once(',') else {
+        return false;
+    };
+    let Some((mime, encoding)) = header.split_once(';') else {
+        return false;
+    };
+    encoding.eq_ignore_ascii_case("base64")
+        && DATA_IMAGE_TYPES
+            .iter()
+            .any(|allowed| mime.eq_ignore_ascii_case(allowed))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hrefs(html: &str) -> Vec<String> {
+        html.split(" href=\"")
+            .chain(html.split(" src=\""))
+            .filter(|part| !part.starts_with('<'))
+            .filter_map(|part| part.split('"').next())
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn script_urls_are_removed_from_links_and_images() {
+        let html = render(
+            "t",
+            "[a](javascript:alert(1)) [b](JaVaScRiPt:alert(1)) [c](<java\tscript:alert(1)>) \
+             [d](&#106;avascript:alert(1)) <javascript:alert(1)> \
+             ![e](data:text/html;base64,PHNjcmlwdD4=) [f](vbscript:msgbox)",
+        );
+        assert!(hrefs(&html).iter().all(String::is_empty), "{}", html);
+    }
+
+    #[test]
+    fn safe_urls_are_kept() {
+        let html = render(
+            "t",
+            "[a](https://example.com) [b](http://example.com) [c](mailto:me@example.com) \
+             [d](docs/a:b.md) [e](../readme.md) [f](#section) ![g](images/shot.png)",
+        );
+        assert_eq!(
+            hrefs(&html),
+            [
+                "https://example.com",
+                "http://example.com",
+                "mailto:me@example.com",
+                "docs/a:b.md",
+                "../readme.md",
+                "#section",
+                "images/shot.png",
+            ]
+        );
+    }
+
+    #[test]
+    fn embedded_raster_images_are_kept_but_other_data_urls_are_not() {
+        let html = render(
+            "t",
+            "![a](data:image/png;base64,iVBORw0KGgo=) ![b](data:image/JPEG;base64,/9j/4AA=) \
+             ![c](data:image/svg+xml;base64,PHN2Zz4=) ![d](data:text/html;base64,PHNjcmlwdD4=) \
+             ![e](data:image/png,raw) [f](data:image/png;base64,iVBORw0KGgo=)",
+        );
+        assert_eq!(
+            hrefs(&html),
+            [
+                "",
+                "data:image/png;base64,iVBORw0KGgo=",
+                "data:image/JPEG;base64,/9j/4AA=",
+                "",
+                "",
+                "",
+            ]
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use crate::files::format_size;
+use crate::history::HistoryEntry;
+use crate::types::{Attachment, AttachmentContent, EnvContext, Mention, Symbol};
+
+/// Markdown 的輸出選項
+#[derive(Debug, Clone, Copy)]
+pub(super) struct RenderOptions {
+    /// 內嵌圖片的 base64；false 時只列出名稱
+    pub embed_images: bool,
+    /// 包含本機環境：工作目錄、主機與用戶、SSH 來源、tmux / screen、容器與程序鏈
+    pub local_env: bool,
+}
+
+impl RenderOptions {
+    /// 匯出檔：完整內容
+    pub const FULL: Self = Self {
+        embed_images: true,
+        local_env: true,
+    };
+}
+
+/// 把一次互動整理為 Markdown：訊息、段落與選擇狀態、用戶回覆、引用、附件與環境資訊
+///
+/// 訊息、段落與環境的格式與前端的 `buildMarkdown` 相同，段落的選擇狀態取自回應。
+pub(super) fn render(entry: &HistoryEntry, options: RenderOptions) -> String {
+    let request = &entry.request;
+    let response = &entry.response;
+    let mut out = String::new();
+
+    let status = if response.confirmed { "✅ Confirmed" } else { "❌ Cancelled" };
+    out.push_str(&format!(
+        "- Requested: {}\n- Answered: {}\n- Status: {}\n\n---\n\n",
+        entry.created_at.format("%Y-%m-%d %H:%M:%S"),
+        entry.answered_at.format("%Y-%m-%d %H:%M:%S"),
+        status
+    ));
+
+    if request.is_markdown {
+        out.push_str(request.message.trim_end());
+        out.push('\n');
+    } else {
+        push_fenced(&mut out, &request.message);
+    }
+
+    if !request.sections.is_empty() {
+        out.push_str("\n---\n\n## Sections\n\n");
+        for (i, section) in request.sections.iter().enumerate() {
+            let selected = response.confirmed && response.selected_sections.contains(&i);
+            out.push_str(&format!(
+                "### {} {}\n\n{}\n\n",
+                if selected { "✅" } else { "⬜" },
+                section.title,
+                section.content.trim_end()
+            ));
+        }
+    }
+
+    push_response(&mut out, entry, options.embed_images);
+
+    let env_lines = request
+        .env_context
+        .as_ref()
+        .map(|env| env_lines(env, options.local_env))
+        .unwrap_or_default();
+    if !env_lines.is_empty() {
+        out.push_str("\n---\n\n## Environment\n\n");
+        for line in env_lines {
+            out.push_str(&format!("- {}\n", line));
+        }
+    }
+
+    out
+}
+
+/// 用戶輸入、圖片、`@` 引用、`#` 符號與附件，都沒有時不輸出
+fn push_response(out: &mut String, entry: &HistoryEntry, embed_images: bool) {
+    let response = &entry.response;
+    if response.user_input.trim().is_empty()
+        && response.images.is_empty()
+        && response.mentions.is_empty()
+        && response.symbols.is_empty()
+        && response.attachments.is_empty()
+    {
+        return;
+    }
+
+    out.push_str("\n---\n\n## Response\n\n");
+    if !response.user_input.trim().is_empty() {
+        out.push_str(response.user_input.trim_end());
+        out.push_str("\n\n");
+    }
+
+    if !response.images.is_empty() {
+        out.push_str("### Images\n\n");
+        for (i, image) in response.images.iter().enumerate() {
+            if !embed_images {
+                out.push_str(&format!("- Image {}（not embedded）\n", i + 1));
+                continue;
+            }
+            let src = if image.starts_with("data:") {
+                image.clone()
+            } else {
+                format!("data:image/png;base64,{}", image)
+            };
+            out.push_str(&format!("![Image {}]({})\n\n", i + 1, src));
+        }
+    }
+
+    if !response.mentions.is_empty() {
+        out.push_str("### Mentions\n\n");
+        for mention in &response.mentions {
+            out.push_str(&format!("- {}\n", mention_line(mention)));
+            if let Some(content) = &mention.content {
+                out.push('\n');
+                push_fenced(out, content);
+            }
+        }
+        out.push('\n');
+    }
+
+    if !response.symbols.is_empty() {
+        out.push_str("### Symbols\n\n");
+        for symbol in &response.symbols {
+            out.push_str(&format!("- {}\n", symbol_line(symbol)));
+        }
+        out.push('\n');
+    }
+
+    if !response.attachments.is_empty() {
+        out.push_str("### Attachments\n\n");
+        for attachment in &response.attachments {
+            out.push_str(&format!("- {}\n", attachment_line(attachment, embed_images)));
+            match &attachment.content {
+                Some(AttachmentContent::Text(text)) => {
+                    out.push('\n');
+                    push_fenced(out, text);
+                }
+                Some(AttachmentContent::Base64(data))
+                    if embed_images && attachment.mime.starts_with("image/") =>
+                {
+                    out.push_str(&format!(
+                        "\n  ![{}](data:{};base64,{})\n\n",
+                        attachment.name, attachment.mime, data
+                    ));
+                }
+                _ => {}
+            }
+        }
+        out.push('\n');
+    }
+}
+
+/// 例如「`@main.rs` → `src/main.rs:40-60`（file, 1.2 KB, git: M）」
+fn mention_line(mention: &Mention) -> String {
+    let mut details = vec![if mention.is_directory { "directory" } else { "file" }.to_string()];
+    if let Some(size) = mention.size {
+        details.push(format_size(size));
+    }
+    if let Some(status) = &mention.git_status {
+        details.push(format!("git: {}", status.trim()));
+    }
+    let location = match mention.lines {
+        Some(range) => format!("{}:{}-{}", mention.relative, range.start, range.end),
+        None => mention.relative.clone(),
+    };
+    format!("`@{}` → `{}`（{}）", mention.text, location, details.join(", "))
+}
+
+/// 例如「`handle_confirm`（method, McpServer）`src/mcp/server.rs:240-310`」
+fn symbol_line(symbol: &Symbol) -> String {
+    let details = match &symbol.container {
+        Some(container) => format!("{}, {}", symbol.kind, container),
+        None => symbol.kind.clone(),
+    };
+    format!(
+        "`{}`（{}）`{}:{}-{}`",
+        symbol.name, details, symbol.relative, symbol.lines.start, symbol.lines.end
+    )
+}
+
+/// 例如「app.log（text/plain, 12.0 KB, truncated）→ `logs/app.log`」
+fn attachment_line(attachment: &Attachment, embed_images: bool) -> String {
+    let mut details = vec![attachment.mime.clone(), format_size(attachment.size)];
+    if attachment.truncated {
+        details.push("truncated".to_string());
+    }
+    let embedded = embed_images && attachment.mime.starts_with("image/");
+    if matches!(&attachment.content, Some(AttachmentContent::Base64(_))) && !embedded {
+        details.push("binary content omitted".to_string());
+    }
+    let location = attachment.relative.as_ref().or(attachment.path.as_ref());
+    match location {
+        Some(location) => format!("{}（{}）→ `{}`", attachment.name, details.join(", "), location),
+        None => format!("{}（{}）", attachment.name, details.join(", ")),
+    }
+}
+
+/// 環境資訊的各行，`local_env` 為 true 時與前端的 `formatEnvContext` 相同
+fn env_lines(env: &EnvContext, local_env: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    if let Some(client) = &env.client {
+        let name = client.title.as_deref().unwrap_or(&client.name);
+        match &client.version {
+            Some(version) => lines.push(format!("Client: {} {}", name, version)),
+            None => lines.push(format!("Client: {}", name)),
+        }
+    }
+    if let Some(project) = &env.project_name {
+        lines.push(format!("Project: {}", project));
+    }
+    if let Some(cwd) = env.cwd.as_ref().filter(|_| local_env) {
+        lines.push(format!("Directory: `{}`", cwd));
+    }
+
+    if let Some(session) = env.session.as_ref().filter(|_| local_env) {
+        if let Some(hostname) = &session.hostname {
+            match &session.user {
+                Some(user) => lines.push(format!("Host: {}@{}", user, hostname)),
+                None => lines.push(format!("Host: {}", hostname)),
+            }
+        }
+        if let Some(ssh) = &session.ssh {
+            lines.push(format!("SSH: {}", ssh));
+        }
+        if let Some(tmux) = &session.tmux {
+            lines.push(format!("tmux: {}", tmux.target.as_deref().unwrap_or(&tmux.pane_id)));
+        }
+        if let Some(screen) = &session.screen {
+            lines.push(format!("screen: {}", screen));
+        }
+        if let Some(container) = &session.container {
+            lines.push(format!("Container: {}", container));
+        }
+        if !session.process_chain.is_empty() {
+            let chain: Vec<String> = session
+                .process_chain
+                .iter()
+                .map(|p| format!("{} ({})", p.name, p.pid))
+                .collect();
+            lines.push(format!("Processes: {}", chain.join(" ← ")));
+        }
+    }
+
+    if let Some(git) = &env.git {
+        let mut line = format!("Git: {}", git.branch.as_deref().unwrap_or("detached"));
+        if let Some(head) = &git.head {
+            line.push_str(&format!(" @ {}", head));
+        }
+        if let Some(dirty) = git.dirty_files.filter(|&n| n > 0) {
+            line.push_str(&format!(", {} changed file(s)", dirty));
+        }
+        let (ahead, behind) = (git.ahead.unwrap_or(0), git.behind.unwrap_or(0));
+        if ahead > 0 || behind > 0 {
+            line.push_str(&format!(", ↑{} ↓{}", ahead, behind));
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// 以比內容中最長的反引號串更長的 fence 包住內容
+fn push_fenced(out: &mut String, content: &str) {
+    let longest = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat((longest + 1).max(3));
+    out.push_str(&format!("{}\n{}\n{}\n\n", fence, content.trim_end_matches('\n'), fence));
+}
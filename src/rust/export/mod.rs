@@ -0,0 +1,321 @@
+mod html;
+mod markdown;
+mod pdf;
+mod sessions;
+
+pub use sessions::record_session;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, ExportConfig};
+use crate::history::HistoryEntry;
+
+/// 檔名的最大長度（bytes，不含重複時加上的後綴）
+const MAX_FILE_NAME_BYTES: usize = 200;
+/// 清理後為空時使用的檔名
+const FALLBACK_STEM: &str = "claude-confirm-export";
+/// Windows 保留的裝置名稱（不分大小寫，帶副檔名也一樣無法建立）
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// 後端可直接產生的匯出格式（PNG 需要畫面截圖，只能由前端匯出）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Md,
+    Html,
+    Pdf,
+}
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Some(Self::Md),
+            "html" | "htm" => Some(Self::Html),
+            "pdf" => Some(Self::Pdf),
+            _ => None,
+        }
+    }
+
+    /// 對應 [`save`] 的 `file_type`
+    pub fn file_type(self) -> &'static str {
+        match self {
+            Self::Md => "md",
+            Self::Html => "html",
+            Self::Pdf => "pdf",
+        }
+    }
+}
+
+/// 把一次互動渲染為指定格式
+pub fn render(entry: &HistoryEntry, format: ExportFormat, config: &ExportConfig) -> Result<Vec<u8>> {
+    let markdown = markdown::render(entry, markdown::RenderOptions::FULL);
+    match format {
+        ExportFormat::Md => Ok(markdown.into_bytes()),
+        ExportFormat::Html => Ok(html::render(&title(entry), &markdown).into_bytes()),
+        ExportFormat::Pdf => pdf::render(&title(entry), &markdown, config),
+    }
+}
+
+/// 匯出檔的預設檔名（不含副檔名），與前端匯出的命名方式相同
+pub fn file_stem(entry: &HistoryEntry) -> String {
+    let time = entry.answered_at.format("%Y-%m-%d-%H%M%S");
+    let project = project_name(entry).map(|name| {
+        name.chars()
+            .map(|c| if c.is_alphanumeric() || matches!(c, '-' | '_') { c } else { '-' })
+            .collect::<String>()
+    });
+    match project {
+        Some(project) => format!("claude-confirm-{}-{}", project, time),
+        None => format!("claude-confirm-{}", time),
+    }
+}
+
+/// 依設定把剛完成的互動封存到 `export.archive_dir`，`export.archive` 為空時不做事
+///
+/// 回傳寫入的檔案；個別格式失敗時記錄後繼續其他格式。
+pub fn archive(entry: &HistoryEntry) -> Result<Vec<PathBuf>> {
+    let config = Config::load().export;
+    if config.archive.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let dir = match &config.archive_dir {
+        Some(dir) => expand_home(dir),
+        None => dirs::data_local_dir()
+            .context("無法取得封存目錄")?
+            .join("claude-confirm")
+            .join("archive"),
+    };
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("無法建立封存目錄: {}", dir.display()))?;
+
+    let stem = file_stem(entry);
+    let mut written = Vec::new();
+    for &format in &config.archive {
+        let result = render(entry, format, &config)
+            .and_then(|data| write_unique(&dir, &stem, format.file_type(), &data));
+        match result {
+            Ok(path) => written.push(path),
+            Err(e) => log::warn!("封存 {} 失敗: {:#}", format.file_type(), e),
+        }
+    }
+    Ok(written)
+}
+
+fn title(entry: &HistoryEntry) -> String {
+    match project_name(entry) {
+        Some(project) => format!("Claude Confirm — {}", project),
+        None => "Claude Confirm".to_string(),
+    }
+}
+
+/// `project_name`，沒有時取 cwd 的最後一段
+fn project_name(entry: &HistoryEntry) -> Option<String> {
+    let env = entry.request.env_context.as_ref()?;
+    env.project_name.clone().or_else(|| {
+        let cwd = env.cwd.as_deref()?;
+        Path::new(cwd).file_name().map(|name| name.to_string_lossy().to_string())
+    })
+}
+
+/// 各匯出類型允許的副檔名，第一個為預設
+fn allowed_extensions(file_type: &str) -> Option<&'static [&'static str]> {
+    match file_type {
+        "png" => Some(&["png"]),
+        "pdf" => Some(&["pdf"]),
+        "md" => Some(&["md", "markdown"]),
+        "html" => Some(&["html", "htm"]),
+        _ => None,
+    }
+}
+
+/// 匯出目錄：設定檔的 `export.dir`，未設定時為 Downloads（沒有時為家目錄）
+pub fn export_dir(config: &ExportConfig) -> Result<PathBuf> {
+    let dir = match &config.dir {
+        Some(dir) => expand_home(dir),
+        None => dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .context("無法取得 Downloads 目錄")?,
+    };
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("無法建立匯出目錄: {}", dir.display()))?;
+    Ok(dir)
+}
+
+/// 把匯出內容寫入匯出目錄，回傳實際寫入的路徑
+///
+/// 檔名只取最後一段並移除不安全的字元，副檔名必須符合 `file_type`（不符時補上）。
+/// 同名檔案存在時加上 `-1`、`-2`… 後綴，不會覆蓋；先寫入暫存檔再連結到目標路徑。
+pub fn save(
+    config: &ExportConfig,
+    filename: &str,
+    file_type: &str,
+    data: &[u8],
+) -> Result<PathBuf> {
+    let Some(extensions) = allowed_extensions(file_type) else {
+        bail!("不支援的匯出類型: {}", file_type);
+    };
+    let (stem, extension) = sanitize_file_name(filename, extensions);
+    let dir = export_dir(config)?;
+    write_unique(&dir, &stem, extension, data)
+}
+
+/// 先寫入暫存檔再連結到 `dir` 中不存在的檔名，回傳實際寫入的路徑
+fn write_unique(dir: &Path, stem: &str, extension: &str, data: &[u8]) -> Result<PathBuf> {
+    let temp = dir.join(format!(".{}.{}.tmp", stem, uuid::Uuid::new_v4().simple()));
+    let result = write_temp(&temp, data).and_then(|()| link_unique(&temp, dir, stem, extension));
+    let _ = std::fs::remove_file(&temp);
+    result
+}
+
+/// 清理檔名，回傳（主檔名, 副檔名）
+fn sanitize_file_name<'a>(filename: &str, extensions: &'a [&'a str]) -> (String, &'a str) {
+    // 只取最後一段，忽略前端傳來的任何目錄
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or_default();
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' => '-',
+            c if c.is_control() => '-',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim().trim_start_matches('.').trim_end_matches(['.', ' ']);
+
+    let (stem, extension) = match cleaned.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => {
+            match extensions.iter().find(|allowed| allowed.eq_ignore_ascii_case(ext)) {
+                Some(allowed) => (stem, *allowed),
+                // 副檔名不符時保留原本的名稱，補上正確的副檔名
+                None => (cleaned, extensions[0]),
+            }
+        }
+        _ => (cleaned, extensions[0]),
+    };
+
+    let mut stem = stem.trim_end_matches(['.', ' ']).to_string();
+    if stem.is_empty() {
+        stem = FALLBACK_STEM.to_string();
+    }
+    if is_reserved_name(&stem) {
+        stem.insert(0, '_');
+    }
+    if stem.len() > MAX_FILE_NAME_BYTES {
+        let mut end = MAX_FILE_NAME_BYTES;
+        while !stem.is_char_boundary(end) {
+            end -= 1;
+        }
+        stem.truncate(end);
+        // 截斷後結尾可能又是點或空白
+        stem.truncate(stem.trim_end_matches(['.', ' ']).len());
+    }
+    (stem, extension)
+}
+
+/// 第一個點之前的部分是 Windows 保留的裝置名稱（例如 `con`、`NUL.tar`、`com1 `）
+fn is_reserved_name(stem: &str) -> bool {
+    let base = stem.split('.').next().unwrap_or_default().trim_end();
+    RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(base))
+}
+
+fn write_temp(path: &Path, data: &[u8]) -> Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .with_context(|| format!("無法建立暫存檔: {}", path.display()))?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// 以 hard link 把暫存檔放到不存在的檔名上（目標已存在時 link 會失敗，不會覆蓋）
+///
+/// 檔案系統不支援 hard link 時改用 rename，先確認目標不存在。
+fn link_unique(temp: &Path, dir: &Path, stem: &str, extension: &str) -> Result<PathBuf> {
+    for n in 0..1000 {
+        let name = match n {
+            0 => format!("{}.{}", stem, extension),
+            n => format!("{}-{}.{}", stem, n, extension),
+        };
+        let target = dir.join(name);
+        match std::fs::hard_link(temp, &target) {
+            Ok(()) => return Ok(target),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => {
+                log::debug!("無法建立 hard link，改用 rename: {}", e);
+                if target.exists() {
+                    continue;
+                }
+                std::fs::rename(temp, &target)
+                    .with_context(|| format!("寫入檔案失敗: {}", target.display()))?;
+                return Ok(target);
+            }
+        }
+    }
+    bail!("{} 中已有太多同名檔案: {}.{}", dir.display(), stem, extension)
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MD: &[&str] = &["md", "markdown"];
+
+    fn sanitize(filename: &str) -> String {
+        let (stem, extension) = sanitize_file_name(filename, MD);
+        format!("{}.{}", stem, extension)
+    }
+
+    #[test]
+    fn strips_directories_and_invalid_characters() {
+        assert_eq!(sanitize("../../etc/passwd"), "passwd.md");
+        assert_eq!(sanitize("C:\\Users\\me\\notes.md"), "notes.md");
+        assert_eq!(sanitize("a<b>c:d?.md"), "a-b-c-d-.md");
+        assert_eq!(sanitize("summary.MARKDOWN"), "summary.markdown");
+        assert_eq!(sanitize("summary.txt"), "summary.txt.md");
+    }
+
+    #[test]
+    fn reserved_windows_names_are_prefixed() {
+        assert_eq!(sanitize("CON.md"), "_CON.md");
+        assert_eq!(sanitize("nul"), "_nul.md");
+        assert_eq!(sanitize("com1.tar.md"), "_com1.tar.md");
+        assert_eq!(sanitize("lpt9 .md"), "_lpt9.md");
+        assert_eq!(sanitize("console.md"), "console.md");
+    }
+
+    #[test]
+    fn trailing_dots_and_spaces_are_removed() {
+        assert_eq!(sanitize("report. .md"), "report.md");
+        assert_eq!(sanitize("report . "), "report.md");
+        assert_eq!(sanitize("..."), "claude-confirm-export.md");
+    }
+
+    #[test]
+    fn long_names_are_truncated_on_char_boundaries() {
+        let (stem, _) = sanitize_file_name(&"測".repeat(100), MD);
+        assert!(stem.len() <= MAX_FILE_NAME_BYTES);
+        assert!(stem.chars().all(|c| c == '測'));
+
+        // 截斷在 `a…a.` 之後，結尾的點也要移除
+        let name = format!("{}. x", "a".repeat(MAX_FILE_NAME_BYTES - 1));
+        let (stem, _) = sanitize_file_name(&name, MD);
+        assert_eq!(stem, "a".repeat(MAX_FILE_NAME_BYTES - 1));
+    }
+}
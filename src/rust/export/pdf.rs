@@ -0,0 +1,314 @@
+use anyhow::{anyhow, bail, Context, Result};
+use printpdf::{
+    BuiltinFont, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference,
+    Point,
+};
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::path::PathBuf;
+
+use crate::config::ExportConfig;
+
+/// A4（mm）
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 18.0;
+/// 1 pt 等於多少 mm
+const PT_TO_MM: f32 = 0.3528;
+const BODY_SIZE: f32 = 10.0;
+const CODE_SIZE: f32 = 8.5;
+/// 清單每層的縮排（mm）
+const LIST_INDENT: f32 = 5.0;
+
+/// 未設定 `export.pdf_font` 時依序尋找的字型，先找支援中文的字型，再找只有拉丁字元的
+///
+/// printpdf 會嵌入整個字型檔，因此優先選擇較小的字型；TTC 字型集合無法直接嵌入，不列入。
+const FONT_CANDIDATES: &[&str] = &[
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+    "/usr/share/fonts/google-droid-sans-fonts/DroidSansFallbackFull.ttf",
+    "/usr/share/fonts/droid/DroidSansFallbackFull.ttf",
+    "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+    "/Library/Fonts/Arial Unicode.ttf",
+    r"C:\Windows\Fonts\kaiu.ttf",
+    r"C:\Windows\Fonts\simhei.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu-sans-fonts/DejaVuSans.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    r"C:\Windows\Fonts\arial.ttf",
+];
+
+/// 版面中的一個區塊
+enum Block {
+    Heading(f32, String),
+    Text { indent: f32, quote: bool, text: String },
+    Code(String),
+    Rule,
+}
+
+/// 以簡單的文字排版把 Markdown 轉為 PDF（標題、段落、清單、引言、程式碼區塊、分隔線）
+///
+/// 不需要 WebView，供 CLI 與自動封存使用；圖片只保留替代文字。
+pub(super) fn render(title: &str, markdown: &str, config: &ExportConfig) -> Result<Vec<u8>> {
+    let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "content");
+    let font = load_font(&doc, config)?;
+    let mut writer = Writer {
+        layer: doc.get_page(page).get_layer(layer),
+        doc: &doc,
+        font,
+        y: PAGE_HEIGHT - MARGIN,
+    };
+
+    for block in blocks(markdown) {
+        match block {
+            Block::Heading(size, text) => {
+                writer.space(size * PT_TO_MM * 0.8);
+                writer.paragraph(&text, size, 0.0);
+                writer.space(1.5);
+            }
+            Block::Text { indent, quote, text } => {
+                let text = if quote { format!("> {}", text) } else { text };
+                writer.paragraph(&text, BODY_SIZE, indent);
+                writer.space(1.5);
+            }
+            Block::Code(code) => {
+                writer.paragraph(&code.replace('\t', "    "), CODE_SIZE, LIST_INDENT);
+                writer.space(2.0);
+            }
+            Block::Rule => {
+                writer.space(2.0);
+                writer.rule();
+                writer.space(4.0);
+            }
+        }
+    }
+
+    doc.save_to_bytes().map_err(|e| anyhow!("產生 PDF 失敗: {}", e))
+}
+
+/// 依序排版文字，超出頁面時換頁
+struct Writer<'a> {
+    doc: &'a PdfDocumentReference,
+    layer: PdfLayerReference,
+    font: IndirectFontRef,
+    /// 下一行的上緣（mm，由頁面底部起算）
+    y: f32,
+}
+
+impl Writer<'_> {
+    fn paragraph(&mut self, text: &str, size: f32, indent: f32) {
+        let width = PAGE_WIDTH - 2.0 * MARGIN - indent;
+        let line_height = size * PT_TO_MM * 1.5;
+        for line in wrap(text, size, width) {
+            if self.y - line_height < MARGIN {
+                self.new_page();
+            }
+            self.y -= line_height;
+            self.layer
+                .use_text(line, size, Mm(MARGIN + indent), Mm(self.y), &self.font);
+        }
+    }
+
+    fn rule(&mut self) {
+        let points = vec![
+            (Point::new(Mm(MARGIN), Mm(self.y)), false),
+            (Point::new(Mm(PAGE_WIDTH - MARGIN), Mm(self.y)), false),
+        ];
+        self.layer.set_outline_thickness(0.3);
+        self.layer.add_line(Line {
+            points,
+            is_closed: false,
+        });
+    }
+
+    fn space(&mut self, mm: f32) {
+        self.y -= mm;
+    }
+
+    fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "content");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+}
+
+/// 把 Markdown 事件整理為版面區塊
+fn blocks(markdown: &str) -> Vec<Block> {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut blocks = Vec::new();
+    let mut text = String::new();
+    let mut heading: Option<f32> = None;
+    let mut in_code = false;
+    let mut quote_depth = 0usize;
+    // 每層清單的下一個編號（無序清單為 None）
+    let mut lists: Vec<Option<u64>> = Vec::new();
+
+    for event in Parser::new_ext(markdown, options) {
+        let indent = lists.len() as f32 * LIST_INDENT;
+        let quote = quote_depth > 0;
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                flush(&mut blocks, &mut text, None, indent, quote);
+                heading = Some(match level {
+                    HeadingLevel::H1 => 16.0,
+                    HeadingLevel::H2 => 13.5,
+                    HeadingLevel::H3 => 12.0,
+                    _ => 11.0,
+                });
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                flush(&mut blocks, &mut text, heading, indent, quote);
+                heading = None;
+            }
+            Event::Start(Tag::List(start)) => {
+                flush(&mut blocks, &mut text, None, indent, quote);
+                lists.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                flush(&mut blocks, &mut text, None, indent, quote);
+                lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                flush(&mut blocks, &mut text, None, indent, quote);
+                match lists.last_mut() {
+                    Some(Some(n)) => {
+                        text.push_str(&format!("{}. ", n));
+                        *n += 1;
+                    }
+                    _ => text.push_str("• "),
+                }
+            }
+            Event::End(TagEnd::Item | TagEnd::Paragraph | TagEnd::TableRow | TagEnd::TableHead) => {
+                flush(&mut blocks, &mut text, None, indent, quote);
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                flush(&mut blocks, &mut text, None, indent, quote);
+                in_code = true;
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                let code = std::mem::take(&mut text);
+                blocks.push(Block::Code(code.trim_end_matches('\n').to_string()));
+                in_code = false;
+            }
+            Event::Start(Tag::BlockQuote(_)) => {
+                flush(&mut blocks, &mut text, None, indent, quote);
+                quote_depth += 1;
+            }
+            Event::End(TagEnd::BlockQuote(_)) => {
+                flush(&mut blocks, &mut text, None, indent, quote);
+                quote_depth = quote_depth.saturating_sub(1);
+            }
+            Event::End(TagEnd::TableCell) => text.push_str(" | "),
+            Event::Text(t) | Event::Code(t) | Event::Html(t) | Event::InlineHtml(t) => {
+                text.push_str(&t)
+            }
+            Event::SoftBreak => text.push(if in_code { '\n' } else { ' ' }),
+            Event::HardBreak => text.push('\n'),
+            Event::TaskListMarker(checked) => text.push_str(if checked { "[x] " } else { "[ ] " }),
+            Event::Rule => {
+                flush(&mut blocks, &mut text, None, indent, quote);
+                blocks.push(Block::Rule);
+            }
+            _ => {}
+        }
+    }
+    flush(&mut blocks, &mut text, None, 0.0, false);
+
+    blocks
+}
+
+/// 把累積的文字輸出為一個區塊
+fn flush(blocks: &mut Vec<Block>, text: &mut String, heading: Option<f32>, indent: f32, quote: bool) {
+    let content = std::mem::take(text);
+    let content = content.trim();
+    if content.is_empty() {
+        return;
+    }
+    blocks.push(match heading {
+        Some(size) => Block::Heading(size, content.to_string()),
+        None => Block::Text {
+            indent,
+            quote,
+            text: content.to_string(),
+        },
+    });
+}
+
+/// 依寬度換行：英文在空白處斷行，中文等全形字元可在任意位置斷行
+fn wrap(text: &str, size: f32, width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for raw in text.split('\n') {
+        let mut line = String::new();
+        let mut line_width = 0.0;
+        let mut last_space: Option<usize> = None;
+
+        for c in raw.chars().filter(|c| !c.is_control()) {
+            let w = char_width(c, size);
+            if line_width + w > width && !line.is_empty() {
+                match last_space.filter(|&i| i > 0) {
+                    Some(i) => {
+                        let rest = line.split_off(i);
+                        lines.push(line);
+                        line = rest.trim_start().to_string();
+                    }
+                    None => lines.push(std::mem::take(&mut line)),
+                }
+                line_width = line.chars().map(|c| char_width(c, size)).sum();
+                last_space = None;
+            }
+            if c == ' ' {
+                last_space = Some(line.len());
+            }
+            line.push(c);
+            line_width += w;
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// 估計字元寬度（mm）：全形字元約一個字寬，其他約半個字寬
+fn char_width(c: char, size: f32) -> f32 {
+    let em = if is_wide(c) { 1.0 } else { 0.55 };
+    em * size * PT_TO_MM
+}
+
+fn is_wide(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1100..=0x115F
+            | 0x2E80..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1FAFF
+            | 0x20000..=0x3FFFD
+    )
+}
+
+/// 載入 `export.pdf_font` 或第一個找到的系統字型，都沒有時使用內建的 Helvetica
+fn load_font(doc: &PdfDocumentReference, config: &ExportConfig) -> Result<IndirectFontRef> {
+    let path = match &config.pdf_font {
+        Some(path) => Some(super::expand_home(path)),
+        None => FONT_CANDIDATES.iter().map(PathBuf::from).find(|path| path.is_file()),
+    };
+
+    let Some(path) = path else {
+        log::warn!("找不到可嵌入的字型，PDF 改用內建 Helvetica（非拉丁字元無法顯示），可用 export.pdf_font 指定");
+        return doc
+            .add_builtin_font(BuiltinFont::Helvetica)
+            .map_err(|e| anyhow!("載入內建字型失敗: {}", e));
+    };
+
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ttc") || ext.eq_ignore_ascii_case("otc"))
+    {
+        bail!("不支援字型集合（TTC）: {}，請指定單一的 .ttf 字型檔", path.display());
+    }
+    let data = std::fs::read(&path).with_context(|| format!("讀取字型失敗: {}", path.display()))?;
+    doc.add_external_font(data.as_slice())
+        .map_err(|e| anyhow!("載入字型 {} 失敗: {}", path.display(), e))
+}
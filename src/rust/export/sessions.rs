@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use super::markdown;
+use crate::config::{Config, SessionsConfig};
+use crate::files::project_data_dir;
+use crate::history::HistoryEntry;
+
+/// `.claude-confirm/.gitignore` 中由這裡管理的註解前綴
+const GITIGNORE_MARKER: &str = "# claude-confirm sessions:";
+/// 讓 git 追蹤 `sessions/` 的例外規則（`*` 也會符合子目錄中的檔案，兩條都需要）
+const COMMIT_RULES: [&str; 2] = ["!sessions/", "!sessions/**"];
+
+/// 依 `sessions` 設定把互動寫入 `<cwd>/.claude-confirm/sessions/<時間>-<id>.md`
+///
+/// 未啟用或請求沒有 cwd 時回傳 None；寫入後依上限刪除最舊的紀錄。
+pub fn record_session(entry: &HistoryEntry) -> Result<Option<PathBuf>> {
+    let config = Config::load().sessions;
+    if !config.enabled {
+        return Ok(None);
+    }
+    let Some(cwd) = entry.request.env_context.as_ref().and_then(|env| env.cwd.as_deref()) else {
+        return Ok(None);
+    };
+    let root = Path::new(cwd);
+    anyhow::ensure!(root.is_dir(), "工作目錄不存在: {}", cwd);
+
+    let dir = project_data_dir(root, "sessions")?;
+    if let Some(base) = dir.parent() {
+        update_gitignore(&base.join(".gitignore"), config.commit)?;
+    }
+
+    let id: String = entry
+        .id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    let path = dir.join(format!("{}-{}.md", entry.answered_at.format("%Y%m%d-%H%M%S"), id));
+    let options = markdown::RenderOptions {
+        embed_images: config.embed_images,
+        // 會提交到 repo 的紀錄一律不含本機環境
+        local_env: config.env && !config.commit,
+    };
+    std::fs::write(&path, markdown::render(entry, options))
+        .with_context(|| format!("寫入互動紀錄失敗: {}", path.display()))?;
+
+    rotate(&dir, &config);
+    Ok(Some(path))
+}
+
+/// 在 `.claude-confirm/.gitignore` 中寫入提示，`commit` 時加上追蹤 `sessions/` 的例外
+///
+/// 只改動帶有標記的註解與例外規則，保留用戶自行加入的內容。
+fn update_gitignore(path: &Path, commit: bool) -> Result<()> {
+    let original = std::fs::read_to_string(path).unwrap_or_default();
+    let mut lines: Vec<&str> = original
+        .lines()
+        .filter(|line| !line.starts_with(GITIGNORE_MARKER) && !COMMIT_RULES.contains(line))
+        .collect();
+
+    let hint = if commit {
+        format!("{} git 會追蹤 sessions/ 中的互動紀錄（sessions.commit = true）", GITIGNORE_MARKER)
+    } else {
+        format!(
+            "{} 設定 sessions.commit = true 可讓 git 追蹤 sessions/ 中的互動紀錄",
+            GITIGNORE_MARKER
+        )
+    };
+    lines.push(&hint);
+    if commit {
+        lines.extend(COMMIT_RULES);
+    }
+
+    let updated = lines.join("\n") + "\n";
+    if updated != original {
+        std::fs::write(path, updated).with_context(|| format!("無法寫入 {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// 依檔名（時間開頭）由新到舊保留，超過 `max_files` 或 `max_total_bytes` 的舊紀錄刪除
+///
+/// 最新的一筆一定保留，即使本身就超過大小上限。
+fn rotate(dir: &Path, config: &SessionsConfig) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(String, PathBuf, u64)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|entry| {
+            let size = entry.metadata().ok().filter(|m| m.is_file())?.len();
+            Some((entry.file_name().to_string_lossy().to_string(), entry.path(), size))
+        })
+        .collect();
+    files.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let mut total = 0;
+    for (i, (_, path, size)) in files.into_iter().enumerate() {
+        total += size;
+        let over_count = config.max_files > 0 && i >= config.max_files;
+        let over_size = config.max_total_bytes > 0 && total > config.max_total_bytes;
+        if i > 0 && (over_count || over_size) {
+            if let Err(e) = std::fs::remove_file(&path) {
+                log::debug!("刪除舊的互動紀錄 {} 失敗: {}", path.display(), e);
+            }
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::preview::decode_text;
+use super::{project_data_dir, relative_path, resolve_within};
+use crate::config::AttachmentsConfig;
+use crate::types::{Attachment, AttachmentContent};
+
+/// 判斷 MIME 類型時讀取的開頭長度
+const SNIFF_BYTES: u64 = 8 * 1024;
+
+/// 給前端顯示的附件資訊（不含內容）
+#[derive(Debug, Clone, Serialize)]
+pub struct AttachmentSummary {
+    pub id: String,
+    pub name: String,
+    pub mime: String,
+    pub size: u64,
+    /// 專案內的檔案只回傳路徑
+    pub relative: Option<String>,
+    /// 計入預算的內容大小
+    pub embedded_bytes: u64,
+    pub truncated: bool,
+}
+
+/// 請求目前的附件與預算
+#[derive(Debug, Clone, Serialize)]
+pub struct AttachmentList {
+    pub attachments: Vec<AttachmentSummary>,
+    pub used_bytes: u64,
+    pub max_total_bytes: u64,
+    pub max_count: usize,
+    /// 送出時把內容保存到專案的 `.claude-confirm/attachments/`，只回傳路徑
+    pub persist: bool,
+}
+
+/// 各請求尚未送出的附件，送出時整批取出
+#[derive(Clone, Default)]
+pub struct AttachmentStore {
+    requests: Arc<Mutex<HashMap<String, Vec<Attachment>>>>,
+}
+
+impl AttachmentStore {
+    /// 附加檔案：`root` 內的檔案只記錄路徑，其他檔案讀取內容
+    pub fn add_file(
+        &self,
+        request_id: &str,
+        root: Option<&Path>,
+        path: &Path,
+        config: &AttachmentsConfig,
+    ) -> Result<()> {
+        let canonical = path
+            .canonicalize()
+            .with_context(|| format!("找不到檔案: {}", path.display()))?;
+        let display_path = canonical.to_string_lossy().to_string();
+        let duplicate = self.lock().get(request_id).is_some_and(|attachments| {
+            attachments.iter().any(|a| a.path.as_ref() == Some(&display_path))
+        });
+        if duplicate {
+            return Ok(());
+        }
+
+        let in_project =
+            root.and_then(|root| Some((root, resolve_within(root, &canonical).ok()?)));
+        let attachment = match in_project {
+            Some((root, resolved)) => project_file(root, &resolved)?,
+            None => external_file(&canonical, config)?,
+        };
+        self.insert(request_id, attachment, config)
+    }
+
+    /// 附加貼上或從選擇器讀取的內容
+    pub fn add_data(
+        &self,
+        request_id: &str,
+        name: &str,
+        bytes: &[u8],
+        config: &AttachmentsConfig,
+    ) -> Result<()> {
+        let attachment = embed(data_name(name), bytes, bytes.len() as u64, config)?;
+        self.insert(request_id, attachment, config)
+    }
+
+    /// 附加前端以 base64 傳來的內容
+    ///
+    /// 先由編碼長度推算大小，最多只解碼單檔上限的內容：超過上限的文字截斷、其他檔案直接拒絕，
+    /// 不會先解碼整份過大的內容。
+    pub fn add_base64(
+        &self,
+        request_id: &str,
+        name: &str,
+        data: &str,
+        config: &AttachmentsConfig,
+    ) -> Result<()> {
+        let data = data.trim();
+        if !data.is_ascii() {
+            bail!("附件內容不是有效的 base64");
+        }
+        let size = decoded_len(data);
+        let limit = config.max_file_bytes as usize;
+        // 取 4 的倍數長度，解碼後至少有 `limit` bytes 可判斷類型與截斷文字
+        let decode_len = if size > config.max_file_bytes {
+            (limit.div_ceil(3) * 4).min(data.len())
+        } else {
+            data.len()
+        };
+        let bytes = general_purpose::STANDARD
+            .decode(&data[..decode_len])
+            .context("解碼附件失敗")?;
+
+        let attachment = embed(data_name(name), &bytes, size, config)?;
+        self.insert(request_id, attachment, config)
+    }
+
+    pub fn remove(&self, request_id: &str, attachment_id: &str) {
+        if let Some(attachments) = self.lock().get_mut(request_id) {
+            attachments.retain(|a| a.id != attachment_id);
+        }
+    }
+
+    pub fn list(&self, request_id: &str, config: &AttachmentsConfig) -> AttachmentList {
+        let requests = self.lock();
+        let attachments = requests.get(request_id).map(Vec::as_slice).unwrap_or_default();
+        AttachmentList {
+            attachments: attachments
+                .iter()
+                .map(|a| AttachmentSummary {
+                    id: a.id.clone(),
+                    name: a.name.clone(),
+                    mime: a.mime.clone(),
+                    size: a.size,
+                    relative: a.relative.clone(),
+                    embedded_bytes: embedded_bytes(a),
+                    truncated: a.truncated,
+                })
+                .collect(),
+            used_bytes: attachments.iter().map(embedded_bytes).sum(),
+            max_total_bytes: config.max_total_bytes,
+            max_count: config.max_count,
+            persist: config.persist,
+        }
+    }
+
+    /// 請求目前的附件（含內容）
+    pub fn get(&self, request_id: &str) -> Vec<Attachment> {
+        self.lock().get(request_id).cloned().unwrap_or_default()
+    }
+
+    /// 取出請求的所有附件（送出或取消時）
+    pub fn take(&self, request_id: &str) -> Vec<Attachment> {
+        self.lock().remove(request_id).unwrap_or_default()
+    }
+
+    /// 檢查數量與總量上限後加入
+    fn insert(
+        &self,
+        request_id: &str,
+        attachment: Attachment,
+        config: &AttachmentsConfig,
+    ) -> Result<()> {
+        let mut requests = self.lock();
+        let attachments = requests.entry(request_id.to_string()).or_default();
+        if attachments.len() >= config.max_count {
+            bail!("每個請求最多 {} 個附件", config.max_count);
+        }
+        let used: u64 = attachments.iter().map(embedded_bytes).sum();
+        if used + embedded_bytes(&attachment) > config.max_total_bytes {
+            bail!(
+                "附件總量超過上限（{} / {} bytes）: {}",
+                used + embedded_bytes(&attachment),
+                config.max_total_bytes,
+                attachment.name
+            );
+        }
+        attachments.push(attachment);
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Vec<Attachment>>> {
+        self.requests.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 把附件內容保存到 `<root>/.claude-confirm/attachments/<request-id>/`
+///
+/// 保存後附件改為回傳檔案路徑、不再附上內容。
+/// 個別檔案保存失敗時維持原樣，回傳保存的目錄。
+pub fn persist_attachments(
+    root: &Path,
+    request_id: &str,
+    attachments: &mut [Attachment],
+) -> Result<Option<PathBuf>> {
+    if attachments.iter().all(|a| a.content.is_none()) {
+        return Ok(None);
+    }
+    let dir = project_data_dir(root, "attachments")?.join(sanitize_file_name(request_id));
+    std::fs::create_dir_all(&dir)?;
+    let root = root.canonicalize()?;
+
+    for attachment in attachments.iter_mut() {
+        let bytes = match &attachment.content {
+            Some(AttachmentContent::Text(text)) => text.as_bytes().to_vec(),
+            Some(AttachmentContent::Base64(data)) => match general_purpose::STANDARD.decode(data) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    log::warn!("無法解碼附件 {}: {}", attachment.name, e);
+                    continue;
+                }
+            },
+            None => continue,
+        };
+        let path = unique_path(&dir, &sanitize_file_name(&attachment.name));
+        if let Err(e) = std::fs::write(&path, bytes) {
+            log::warn!("保存附件 {} 失敗: {}", path.display(), e);
+            continue;
+        }
+        attachment.relative = Some(relative_path(&root, &path));
+        attachment.path = Some(path.to_string_lossy().to_string());
+        attachment.content = None;
+    }
+
+    Ok(Some(dir))
+}
+
+/// 只保留英數字與 `.-_`，避免路徑分隔符號與隱藏檔
+fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    match sanitized.trim_start_matches('.') {
+        "" => "attachment".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// `dir` 中不與既有檔案重複的路徑（重複時加上 `-1`、`-2`…）
+fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    if !path.exists() {
+        return path;
+    }
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (name, String::new()),
+    };
+    (1..)
+        .map(|n| dir.join(format!("{}-{}{}", stem, n, extension)))
+        .find(|path| !path.exists())
+        .expect("unbounded suffix")
+}
+
+/// 貼上內容的檔名，只取最後一段
+fn data_name(name: &str) -> &str {
+    Path::new(name)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .filter(|n| !n.is_empty())
+        .unwrap_or("pasted")
+}
+
+/// 由 base64 長度推算解碼後的大小
+fn decoded_len(data: &str) -> u64 {
+    let padding = data.bytes().rev().take_while(|&b| b == b'=').count().min(2);
+    ((data.len() / 4 * 3).saturating_sub(padding)) as u64
+}
+
+/// 附件內容佔用的預算（base64 以解碼後的大小計算）
+fn embedded_bytes(attachment: &Attachment) -> u64 {
+    match &attachment.content {
+        Some(AttachmentContent::Text(text)) => text.len() as u64,
+        Some(AttachmentContent::Base64(data)) => (data.len() / 4 * 3) as u64,
+        None => 0,
+    }
+}
+
+/// 專案內的檔案：只記錄路徑
+fn project_file(root: &Path, path: &Path) -> Result<Attachment> {
+    let metadata = std::fs::metadata(path)?;
+    if metadata.is_dir() {
+        bail!("資料夾請以 @ 引用: {}", path.display());
+    }
+    let mut head = Vec::new();
+    std::fs::File::open(path)?
+        .take(SNIFF_BYTES)
+        .read_to_end(&mut head)?;
+    let root = root.canonicalize()?;
+    let name = file_name(path);
+
+    Ok(Attachment {
+        id: uuid::Uuid::new_v4().to_string(),
+        mime: detect_mime(&name, &head),
+        name,
+        size: metadata.len(),
+        path: Some(path.to_string_lossy().to_string()),
+        relative: Some(relative_path(&root, path)),
+        content: None,
+        truncated: false,
+    })
+}
+
+/// 專案外的檔案：讀取（不超過單檔上限的）內容
+fn external_file(path: &Path, config: &AttachmentsConfig) -> Result<Attachment> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("無法開啟檔案: {}", path.display()))?;
+    let metadata = file.metadata()?;
+    if metadata.is_dir() {
+        bail!("專案外的資料夾無法附加: {}", path.display());
+    }
+    let mut bytes = Vec::new();
+    file.take(config.max_file_bytes).read_to_end(&mut bytes)?;
+    embed(&file_name(path), &bytes, metadata.len(), config)
+}
+
+/// 把內容嵌入附件：文字超過上限時截斷，其他檔案超過上限時拒絕
+fn embed(name: &str, bytes: &[u8], size: u64, config: &AttachmentsConfig) -> Result<Attachment> {
+    let head = &bytes[..bytes.len().min(SNIFF_BYTES as usize)];
+    let mime = detect_mime(name, head);
+    let limit = config.max_file_bytes as usize;
+
+    let (content, truncated) = match decode_text(&bytes[..bytes.len().min(limit)]) {
+        Some(text) if !is_binary_mime(&mime) => (
+            AttachmentContent::Text(text.to_string()),
+            size > text.len() as u64,
+        ),
+        _ if size > config.max_file_bytes => {
+            bail!("{} 超過單一附件上限 {} bytes", name, config.max_file_bytes)
+        }
+        _ => (AttachmentContent::Base64(general_purpose::STANDARD.encode(bytes)), false),
+    };
+
+    Ok(Attachment {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        mime,
+        size,
+        path: None,
+        relative: None,
+        content: Some(content),
+        truncated,
+    })
+}
+
+/// 先依檔案開頭的 magic bytes 判斷，再依副檔名，最後依內容是否為文字
+fn detect_mime(name: &str, head: &[u8]) -> String {
+    if let Some(kind) = infer::get(head) {
+        return kind.mime_type().to_string();
+    }
+    match mime_guess::from_path(name).first() {
+        Some(mime) => mime.essence_str().to_string(),
+        None if decode_text(head).is_some() => "text/plain".to_string(),
+        None => "application/octet-stream".to_string(),
+    }
+}
+
+/// 就算內容可解碼為 UTF-8 也應以 base64 保存的類型（例如 SVG 以外的圖片）
+fn is_binary_mime(mime: &str) -> bool {
+    (mime.starts_with("image/") && mime != "image/svg+xml")
+        || mime.starts_with("audio/")
+        || mime.starts_with("video/")
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> AttachmentsConfig {
+        AttachmentsConfig {
+            max_file_bytes: 16,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn oversized_base64_text_is_truncated() {
+        let store = AttachmentStore::default();
+        let data = general_purpose::STANDARD.encode("a".repeat(1000));
+        store.add_base64("r", "notes.txt", &data, &config()).unwrap();
+
+        let attachment = &store.get("r")[0];
+        assert_eq!(attachment.size, 1000);
+        assert!(attachment.truncated);
+        assert!(matches!(
+            &attachment.content,
+            Some(AttachmentContent::Text(text)) if text.len() == 16
+        ));
+    }
+
+    #[test]
+    fn oversized_base64_binary_is_rejected() {
+        let store = AttachmentStore::default();
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.resize(1000, 0);
+        let data = general_purpose::STANDARD.encode(png);
+        assert!(store.add_base64("r", "image.png", &data, &config()).is_err());
+        assert!(store.get("r").is_empty());
+    }
+
+    #[test]
+    fn decoded_len_accounts_for_padding() {
+        for len in 0..10 {
+            let data = general_purpose::STANDARD.encode(vec![0u8; len]);
+            assert_eq!(decoded_len(&data), len as u64);
+        }
+    }
+}
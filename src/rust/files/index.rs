@@ -0,0 +1,273 @@
+use anyhow::Result;
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_matcher::{Matcher, Utf32Str};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::{scan, FileEntry, Listing};
+use crate::config::FilesConfig;
+
+/// 無法監看檔案變動時（例如 inotify 數量不足），快取的有效時間
+const UNWATCHED_TTL: Duration = Duration::from_secs(30);
+/// 最多監看的目錄數，超過時改為定時重新掃描，避免用盡 inotify 的 `max_user_watches`
+const MAX_WATCHED_DIRS: usize = 4096;
+/// 同時保留索引（與監看器）的專案數，超過時移除最久沒用的
+const MAX_ROOTS: usize = 8;
+/// 超過這段時間沒有使用的專案索引會被移除
+const IDLE_EVICT: Duration = Duration::from_secs(30 * 60);
+
+/// 模糊搜尋的一筆結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMatch {
+    #[serde(flatten)]
+    pub entry: FileEntry,
+    pub score: u32,
+    /// `relative` 中符合查詢的字元位置（供前端標示）
+    pub indices: Vec<u32>,
+}
+
+/// 檔案監看器與快取共用的狀態
+#[derive(Default)]
+struct WatchState {
+    /// 已列出的目錄，只有這些目錄下的變動會讓快取失效
+    dirs: HashSet<PathBuf>,
+    stale: bool,
+}
+
+/// 逐一（非遞迴）監看已列出的目錄，被忽略的 target/、node_modules/ 等不會佔用 watch
+struct DirWatcher {
+    watcher: RecommendedWatcher,
+    watched: HashSet<PathBuf>,
+}
+
+impl DirWatcher {
+    fn new(state: Arc<Mutex<WatchState>>) -> notify::Result<Self> {
+        let watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+            let Ok(event) = result else {
+                return;
+            };
+            let structural = matches!(
+                event.kind,
+                EventKind::Create(_)
+                    | EventKind::Remove(_)
+                    | EventKind::Modify(ModifyKind::Name(_))
+            );
+
+            let mut state = lock(&state);
+            if state.stale {
+                return;
+            }
+            let affects_listing = event.paths.iter().any(|path| {
+                // ignore 規則改變時整個列表都可能不同
+                let is_ignore_file = path
+                    .file_name()
+                    .is_some_and(|name| name == ".gitignore" || name == ".ignore");
+                let in_listed_dir = path.parent().is_some_and(|dir| state.dirs.contains(dir));
+                is_ignore_file || (structural && in_listed_dir)
+            });
+            if affects_listing {
+                state.stale = true;
+            }
+        })?;
+        Ok(Self {
+            watcher,
+            watched: HashSet::new(),
+        })
+    }
+
+    /// 讓監看的目錄與 `dirs` 一致；目錄過多或無法新增監看時回傳錯誤
+    fn sync(&mut self, dirs: &HashSet<PathBuf>) -> notify::Result<()> {
+        if dirs.len() > MAX_WATCHED_DIRS {
+            return Err(notify::Error::generic(&format!(
+                "目錄數 {} 超過監看上限 {}",
+                dirs.len(),
+                MAX_WATCHED_DIRS
+            )));
+        }
+
+        let removed: Vec<PathBuf> = self.watched.difference(dirs).cloned().collect();
+        for dir in removed {
+            let _ = self.watcher.unwatch(&dir);
+            self.watched.remove(&dir);
+        }
+        for dir in dirs {
+            if self.watched.contains(dir) {
+                continue;
+            }
+            match self.watcher.watch(dir, RecursiveMode::NonRecursive) {
+                Ok(()) => {
+                    self.watched.insert(dir.clone());
+                }
+                // 掃描後才刪除的目錄，父目錄的事件已讓快取失效
+                Err(e) if is_not_found(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_not_found(error: &notify::Error) -> bool {
+    match &error.kind {
+        notify::ErrorKind::PathNotFound => true,
+        notify::ErrorKind::Io(e) => e.kind() == std::io::ErrorKind::NotFound,
+        _ => false,
+    }
+}
+
+struct RootIndex {
+    listing: Arc<Listing>,
+    scanned_at: Instant,
+    last_used: Instant,
+    /// 掃描時的設定，設定改變時重新掃描
+    config: FilesConfig,
+    state: Arc<Mutex<WatchState>>,
+    /// drop 時停止監看；無法監看時為 None，改為定時重新掃描
+    watcher: Option<DirWatcher>,
+}
+
+impl RootIndex {
+    fn is_fresh(&self, config: &FilesConfig) -> bool {
+        if self.config != *config {
+            return false;
+        }
+        match self.watcher {
+            Some(_) => !lock(&self.state).stale,
+            None => self.scanned_at.elapsed() < UNWATCHED_TTL,
+        }
+    }
+}
+
+/// 依根目錄快取的檔案索引，由檔案監看器在新增、刪除、改名時標記失效
+///
+/// 閒置的專案索引會被移除，常駐的 UI host 不會為每個看過的專案一直保留監看器。
+#[derive(Clone, Default)]
+pub struct FileIndex {
+    roots: Arc<Mutex<HashMap<PathBuf, RootIndex>>>,
+}
+
+impl FileIndex {
+    /// 取得 `root` 的檔案列表，快取失效、設定改變、尚未掃描或 `refresh` 時重新掃描
+    pub fn get(&self, root: &Path, config: &FilesConfig, refresh: bool) -> Result<Arc<Listing>> {
+        if !refresh {
+            if let Some(index) = lock(&self.roots).get_mut(root) {
+                if index.is_fresh(config) {
+                    index.last_used = Instant::now();
+                    return Ok(index.listing.clone());
+                }
+            }
+        }
+
+        let listing = Arc::new(scan(root, config)?);
+        let dirs: HashSet<PathBuf> = listing
+            .entries
+            .iter()
+            .filter(|e| e.is_directory)
+            .map(|e| PathBuf::from(&e.path))
+            .chain(std::iter::once(root.to_path_buf()))
+            .collect();
+
+        let mut roots = lock(&self.roots);
+        let now = Instant::now();
+        let index = roots.entry(root.to_path_buf()).or_insert_with(|| {
+            let state = Arc::new(Mutex::new(WatchState::default()));
+            let watcher = DirWatcher::new(state.clone())
+                .map_err(|e| log::warn!("無法監看 {}，改為定時重新掃描: {}", root.display(), e))
+                .ok();
+            RootIndex {
+                listing: listing.clone(),
+                scanned_at: now,
+                last_used: now,
+                config: config.clone(),
+                state,
+                watcher,
+            }
+        });
+
+        *lock(&index.state) = WatchState {
+            dirs: dirs.clone(),
+            stale: false,
+        };
+        index.listing = listing.clone();
+        index.scanned_at = now;
+        index.last_used = now;
+        index.config = config.clone();
+        if let Some(watcher) = &mut index.watcher {
+            if let Err(e) = watcher.sync(&dirs) {
+                log::warn!("無法監看 {}，改為定時重新掃描: {}", root.display(), e);
+                index.watcher = None;
+            }
+        }
+
+        evict(&mut roots, root);
+        Ok(listing)
+    }
+
+    /// 以模糊比對搜尋相對路徑，依分數排序
+    pub fn search(
+        &self,
+        root: &Path,
+        config: &FilesConfig,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<FileMatch>> {
+        let listing = self.get(root, config, false)?;
+
+        let pattern = Pattern::parse(query, CaseMatching::Smart, Normalization::Smart);
+        let mut matcher = Matcher::new(nucleo_matcher::Config::DEFAULT.match_paths());
+        let mut buf = Vec::new();
+
+        let mut matches: Vec<FileMatch> = listing
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let haystack = Utf32Str::new(&entry.relative, &mut buf);
+                let mut indices = Vec::new();
+                let score = pattern.indices(haystack, &mut matcher, &mut indices)?;
+                indices.sort_unstable();
+                indices.dedup();
+                Some(FileMatch {
+                    entry: entry.clone(),
+                    score,
+                    indices,
+                })
+            })
+            .collect();
+
+        // 分數相同時，較短（較淺）的路徑優先
+        matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.entry.relative.len().cmp(&b.entry.relative.len()))
+        });
+        matches.truncate(limit);
+        Ok(matches)
+    }
+}
+
+/// 移除閒置過久的專案索引，數量超過上限時移除最久沒用的（`current` 除外）
+fn evict(roots: &mut HashMap<PathBuf, RootIndex>, current: &Path) {
+    roots.retain(|root, index| root == current || index.last_used.elapsed() < IDLE_EVICT);
+    while roots.len() > MAX_ROOTS {
+        let oldest = roots
+            .iter()
+            .filter(|(root, _)| root.as_path() != current)
+            .min_by_key(|(_, index)| index.last_used)
+            .map(|(root, _)| root.clone());
+        match oldest {
+            Some(root) => {
+                roots.remove(&root);
+            }
+            None => break,
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,135 @@
+use regex::Regex;
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use super::{relative_path, resolve_within, Listing};
+use crate::git;
+use crate::types::{LineRange, Mention};
+
+/// `@path` 或 `@path:40-60` 形式的引用（`@` 前必須是開頭或空白，避免比對到 email）
+static MENTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|\s)@([\w\-./]+)(?::(\d+)(?:-(\d+))?)?").expect("mention regex")
+});
+
+/// 行範圍引用最多附上的行數
+const MAX_RANGE_LINES: usize = 1000;
+
+/// 解析出的引用
+struct Resolved {
+    text: String,
+    path: PathBuf,
+    lines: Option<LineRange>,
+}
+
+/// 把 `input` 中的 `@` 引用解析為專案內的檔案
+///
+/// `@path:40-60` 會附上該範圍的文字。路徑先當作相對於 `root` 解析；找不到時
+/// （例如舊版只插入檔名）改以檔名比對 `listing`，只有唯一符合時才採用。
+/// 無法解析或不在 `root` 內的引用會略過。
+pub fn resolve_mentions(root: &Path, input: &str, listing: Option<&Listing>) -> Vec<Mention> {
+    let mut seen = HashSet::new();
+    let mut resolved: Vec<Resolved> = Vec::new();
+
+    for capture in MENTION.captures_iter(input) {
+        // 句尾的標點不屬於路徑
+        let name = capture[1].trim_end_matches('.');
+        if name.is_empty() {
+            continue;
+        }
+        let lines = capture.get(2).and_then(|start| {
+            let start: usize = start.as_str().parse().ok()?;
+            let end = match capture.get(3) {
+                Some(end) => end.as_str().parse().ok()?,
+                None => start,
+            };
+            (start >= 1 && end >= start).then_some(LineRange { start, end })
+        });
+        let text = match lines {
+            Some(range) => format!("{}:{}-{}", name, range.start, range.end),
+            None => name.to_string(),
+        };
+        if !seen.insert(text.clone()) {
+            continue;
+        }
+
+        let path = resolve_within(root, Path::new(name.trim_end_matches('/')))
+            .ok()
+            .or_else(|| find_by_name(root, name, listing?));
+        match path {
+            Some(path) => resolved.push(Resolved { text, path, lines }),
+            None => log::debug!("無法解析引用 @{}", text),
+        }
+    }
+
+    let files: Vec<PathBuf> = resolved
+        .iter()
+        .filter(|r| r.path.is_file())
+        .map(|r| r.path.clone())
+        .collect();
+    let statuses = git::file_statuses(root, &files);
+
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    resolved
+        .into_iter()
+        .map(|Resolved { text, path, lines }| {
+            let metadata = std::fs::metadata(&path).ok();
+            let is_directory = metadata.as_ref().is_some_and(|m| m.is_dir());
+            let selection = lines
+                .filter(|_| !is_directory)
+                .and_then(|range| read_lines(&path, range));
+            Mention {
+                text,
+                relative: relative_path(&root, &path),
+                is_directory,
+                size: metadata.filter(|m| m.is_file()).map(|m| m.len()),
+                git_status: statuses.get(&path).cloned(),
+                lines: selection.as_ref().map(|(range, _)| *range),
+                content: selection.map(|(_, content)| content),
+                path: path.to_string_lossy().to_string(),
+            }
+        })
+        .collect()
+}
+
+/// 讀取行範圍內的文字，範圍超出檔案時截到最後一行
+fn read_lines(path: &Path, range: LineRange) -> Option<(LineRange, String)> {
+    let file = std::fs::File::open(path).ok()?;
+    let end = range.end.min(range.start.saturating_add(MAX_RANGE_LINES - 1));
+
+    let lines: Vec<String> = BufReader::new(file)
+        .lines()
+        .skip(range.start - 1)
+        .take(end - range.start + 1)
+        .collect::<std::io::Result<_>>()
+        .ok()?;
+    if lines.is_empty() {
+        log::debug!("{} 沒有第 {} 行", path.display(), range.start);
+        return None;
+    }
+
+    let range = LineRange {
+        start: range.start,
+        end: range.start + lines.len() - 1,
+    };
+    Some((range, lines.join("\n")))
+}
+
+/// 以檔名（或路徑結尾）比對掃描結果，多筆符合時視為無法判斷
+fn find_by_name(root: &Path, text: &str, listing: &Listing) -> Option<PathBuf> {
+    let is_directory = text.ends_with('/');
+    let name = text.trim_end_matches('/');
+    let suffix = format!("/{}", name);
+
+    let mut matches = listing.entries.iter().filter(|entry| {
+        entry.is_directory == is_directory
+            && (entry.relative == name || entry.relative.ends_with(&suffix))
+    });
+    let entry = matches.next()?;
+    if matches.next().is_some() {
+        log::debug!("引用 @{} 符合多個檔案，略過", text);
+        return None;
+    }
+    resolve_within(root, Path::new(&entry.path)).ok()
+}
@@ -0,0 +1,163 @@
+mod attachment;
+mod index;
+mod mention;
+mod preview;
+mod symbols;
+
+pub use attachment::{persist_attachments, AttachmentList, AttachmentStore};
+pub use index::{FileIndex, FileMatch};
+pub use mention::resolve_mentions;
+pub use preview::{preview, FilePreview};
+pub use symbols::{SymbolIndex, SymbolMatch};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config::FilesConfig;
+
+/// 檔案選擇器中的一個項目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEntry {
+    pub path: String,
+    pub name: String,
+    pub is_directory: bool,
+    /// 相對於專案根目錄的路徑（以 `/` 分隔）
+    pub relative: String,
+}
+
+/// 一次掃描的結果
+#[derive(Debug, Clone)]
+pub struct Listing {
+    pub root: PathBuf,
+    pub entries: Vec<FileEntry>,
+    /// 達到 `max_entries` 而提前停止
+    pub truncated: bool,
+}
+
+/// 把 `path`（絕對路徑或相對於 `root`）解析為實際路徑
+///
+/// 拒絕 `root` 以外的路徑，包括經由 `..` 或符號連結離開專案目錄的情況。
+pub fn resolve_within(root: &Path, path: &Path) -> Result<PathBuf> {
+    let root = root
+        .canonicalize()
+        .with_context(|| format!("無法存取專案目錄: {}", root.display()))?;
+    let resolved = root
+        .join(path)
+        .canonicalize()
+        .with_context(|| format!("找不到路徑: {}", path.display()))?;
+    anyhow::ensure!(
+        resolved.starts_with(&root),
+        "路徑 {} 不在專案目錄 {} 內",
+        path.display(),
+        root.display()
+    );
+    Ok(resolved)
+}
+
+/// 專案內保存附件等資料的目錄
+const PROJECT_DATA_DIR: &str = ".claude-confirm";
+
+/// 建立並回傳 `<root>/.claude-confirm/<name>`
+///
+/// 第一次建立時在 `.claude-confirm/` 放入忽略所有內容的 `.gitignore`，避免附件被提交。
+pub fn project_data_dir(root: &Path, name: &str) -> Result<PathBuf> {
+    let base = root.join(PROJECT_DATA_DIR);
+    let dir = base.join(name);
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("無法建立目錄: {}", dir.display()))?;
+
+    let gitignore = base.join(".gitignore");
+    if !gitignore.exists() {
+        std::fs::write(&gitignore, "# claude-confirm 自動產生\n*\n")
+            .with_context(|| format!("無法寫入 {}", gitignore.display()))?;
+    }
+    Ok(dir)
+}
+
+/// 例如「512 B」、「1.2 KB」、「3.4 MB」
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// 掃描專案目錄
+///
+/// 遵守 `.gitignore`、`.ignore`、`.git/info/exclude` 與全域 gitignore（不在 git 儲存庫內也適用），
+/// 深度與數量上限依 [`FilesConfig`]。
+pub fn scan(root: &Path, config: &FilesConfig) -> Result<Listing> {
+    let mut builder = ignore::WalkBuilder::new(root);
+    builder
+        .max_depth(Some(config.max_depth))
+        .hidden(!config.hidden)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .sort_by_file_name(|a, b| a.cmp(b));
+
+    let mut entries = Vec::new();
+    let mut truncated = false;
+
+    for result in builder.build() {
+        let entry = match result {
+            Ok(entry) => entry,
+            Err(e) => {
+                log::debug!("略過無法讀取的項目: {}", e);
+                continue;
+            }
+        };
+        // 根目錄本身
+        if entry.depth() == 0 {
+            continue;
+        }
+        if entries.len() >= config.max_entries {
+            truncated = true;
+            break;
+        }
+
+        let path = entry.path();
+        // 不跟隨符號連結，也不列出指向專案外的連結
+        if entry.path_is_symlink() && resolve_within(root, path).is_err() {
+            continue;
+        }
+
+        entries.push(FileEntry {
+            path: path.to_string_lossy().to_string(),
+            name: entry.file_name().to_string_lossy().to_string(),
+            is_directory: entry.file_type().is_some_and(|t| t.is_dir()),
+            relative: relative_path(root, path),
+        });
+    }
+
+    // 排序：資料夾在前，然後按名稱排序
+    entries.sort_by(|a, b| match (a.is_directory, b.is_directory) {
+        (true, false) => std::cmp::Ordering::Less,
+        (false, true) => std::cmp::Ordering::Greater,
+        _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+    });
+
+    Ok(Listing {
+        root: root.to_path_buf(),
+        entries,
+        truncated,
+    })
+}
+
+/// 相對於 `root` 的路徑，以 `/` 分隔
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
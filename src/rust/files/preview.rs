@@ -0,0 +1,118 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::Path;
+
+use super::{relative_path, resolve_within, scan, FileEntry};
+use crate::config::FilesConfig;
+
+/// 預覽最多讀取的位元組數
+const MAX_PREVIEW_BYTES: u64 = 64 * 1024;
+/// 預覽最多顯示的行數
+const MAX_PREVIEW_LINES: usize = 400;
+/// 資料夾預覽最多列出的項目數
+const MAX_DIRECTORY_ENTRIES: usize = 200;
+/// 判斷是否為二進位檔時檢查的開頭長度
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+/// @ 選擇器中選中項目的預覽
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FilePreview {
+    Text {
+        path: String,
+        relative: String,
+        /// 副檔名，供前端選擇語法高亮
+        language: String,
+        content: String,
+        size: u64,
+        /// 超過大小或行數上限，只包含開頭部分
+        truncated: bool,
+    },
+    Binary {
+        path: String,
+        relative: String,
+        size: u64,
+    },
+    Directory {
+        path: String,
+        relative: String,
+        entries: Vec<FileEntry>,
+        truncated: bool,
+    },
+}
+
+/// 預覽專案內的檔案或資料夾（`path` 可為絕對路徑或相對於 `root`）
+pub fn preview(root: &Path, path: &Path) -> Result<FilePreview> {
+    let resolved = resolve_within(root, path)?;
+    let root = root.canonicalize()?;
+    let relative = relative_path(&root, &resolved);
+    let display_path = resolved.to_string_lossy().to_string();
+
+    if resolved.is_dir() {
+        // 與檔案選擇器相同的 ignore 規則，只列出一層
+        let config = FilesConfig {
+            max_depth: 1,
+            max_entries: MAX_DIRECTORY_ENTRIES,
+            hidden: false,
+        };
+        let listing = scan(&resolved, &config)?;
+        return Ok(FilePreview::Directory {
+            path: display_path,
+            relative,
+            entries: listing.entries,
+            truncated: listing.truncated,
+        });
+    }
+
+    let file = std::fs::File::open(&resolved)
+        .with_context(|| format!("無法開啟檔案: {}", resolved.display()))?;
+    let size = file.metadata()?.len();
+
+    let mut bytes = Vec::new();
+    file.take(MAX_PREVIEW_BYTES).read_to_end(&mut bytes)?;
+
+    let Some(text) = decode_text(&bytes) else {
+        return Ok(FilePreview::Binary {
+            path: display_path,
+            relative,
+            size,
+        });
+    };
+
+    let mut truncated = size > MAX_PREVIEW_BYTES;
+    let content = match text.match_indices('\n').nth(MAX_PREVIEW_LINES - 1) {
+        Some((end, _)) => {
+            truncated = true;
+            text[..end].to_string()
+        }
+        None => text.to_string(),
+    };
+
+    Ok(FilePreview::Text {
+        path: display_path,
+        relative,
+        language: resolved
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_string(),
+        content,
+        size,
+        truncated,
+    })
+}
+
+/// 解碼為 UTF-8 文字，含 NUL 或無效編碼時視為二進位檔
+///
+/// 讀取被截斷時結尾可能是不完整的字元，這種情況只捨棄最後的片段。
+pub(super) fn decode_text(bytes: &[u8]) -> Option<&str> {
+    if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        return None;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some(text),
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&bytes[..e.valid_up_to()]).ok(),
+        Err(_) => None,
+    }
+}
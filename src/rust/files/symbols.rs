@@ -0,0 +1,535 @@
+use anyhow::Result;
+use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_matcher::{Matcher, Utf32Str};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tree_sitter::{Language, Node, Parser};
+
+use super::{resolve_within, Listing};
+use crate::types::{LineRange, Symbol};
+
+/// 超過此大小的檔案不建立符號索引（多半是產生的程式碼）
+const MAX_SOURCE_BYTES: u64 = 512 * 1024;
+
+/// 符號搜尋的一筆結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolMatch {
+    #[serde(flatten)]
+    pub symbol: Symbol,
+    pub score: u32,
+    /// `name` 中符合查詢的字元位置
+    pub indices: Vec<u32>,
+}
+
+/// 支援的語言（依副檔名判斷）
+#[derive(Debug, Clone, Copy)]
+enum Lang {
+    Rust,
+    TypeScript,
+    Tsx,
+    Python,
+}
+
+impl Lang {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Self::Rust),
+            "ts" | "mts" | "cts" | "js" | "mjs" | "cjs" => Some(Self::TypeScript),
+            "tsx" | "jsx" => Some(Self::Tsx),
+            "py" | "pyi" => Some(Self::Python),
+            _ => None,
+        }
+    }
+
+    fn language(self) -> Language {
+        match self {
+            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Self::Python => tree_sitter_python::LANGUAGE.into(),
+        }
+    }
+
+    /// 語法節點對應的符號種類，不是定義的節點回傳 None
+    fn symbol_kind(self, node: Node) -> Option<&'static str> {
+        let kind = match (self, node.kind()) {
+            (Self::Rust, "function_item" | "function_signature_item") => {
+                if node.parent().is_some_and(|p| p.kind() == "declaration_list") {
+                    "method"
+                } else {
+                    "function"
+                }
+            }
+            (Self::Rust, "struct_item") => "struct",
+            (Self::Rust, "enum_item") => "enum",
+            (Self::Rust, "union_item") => "union",
+            (Self::Rust, "trait_item") => "trait",
+            (Self::Rust, "type_item") => "type",
+            (Self::Rust, "const_item") => "const",
+            (Self::Rust, "static_item") => "static",
+            (Self::Rust, "mod_item") => "module",
+            (Self::Rust, "macro_definition") => "macro",
+
+            (Self::TypeScript | Self::Tsx, kind) => match kind {
+                "function_declaration" | "generator_function_declaration" => "function",
+                "class_declaration" | "abstract_class_declaration" => "class",
+                "interface_declaration" => "interface",
+                "type_alias_declaration" => "type",
+                "enum_declaration" => "enum",
+                "method_definition" | "method_signature" | "abstract_method_signature" => "method",
+                // const foo = () => {} / function () {}
+                "variable_declarator"
+                    if node.child_by_field_name("value").is_some_and(|v| {
+                        matches!(v.kind(), "arrow_function" | "function_expression" | "function")
+                    }) =>
+                {
+                    "function"
+                }
+                _ => return None,
+            },
+
+            (Self::Python, "function_definition") => {
+                // 方法位於 class 的 block 內（可能再包一層 decorated_definition）
+                let mut parent = node.parent();
+                if parent.is_some_and(|p| p.kind() == "decorated_definition") {
+                    parent = parent.and_then(|p| p.parent());
+                }
+                if parent
+                    .and_then(|p| p.parent())
+                    .is_some_and(|p| p.kind() == "class_definition")
+                {
+                    "method"
+                } else {
+                    "function"
+                }
+            }
+            (Self::Python, "class_definition") => "class",
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    /// 會成為其子符號 container 的節點名稱（Rust impl 的型別、class、trait、module）
+    fn container_name(self, node: Node, source: &[u8]) -> Option<String> {
+        let name = match (self, node.kind()) {
+            (Self::Rust, "impl_item") => node.child_by_field_name("type")?,
+            (Self::Rust, "trait_item" | "mod_item")
+            | (
+                Self::TypeScript | Self::Tsx,
+                "class_declaration" | "abstract_class_declaration" | "interface_declaration",
+            )
+            | (Self::Python, "class_definition") => node.child_by_field_name("name")?,
+            _ => return None,
+        };
+        name.utf8_text(source).ok().map(String::from)
+    }
+}
+
+/// 解析單一檔案中的符號
+///
+/// 索引與送出時的確認都經過這裡，超過 `MAX_SOURCE_BYTES` 的檔案一律不解析
+/// （最多只讀取上限 + 1 位元組）。
+fn parse_symbols(path: &Path, relative: &str) -> Result<Vec<Symbol>> {
+    let Some(lang) = Lang::from_path(path) else {
+        return Ok(Vec::new());
+    };
+    let mut source = Vec::new();
+    std::fs::File::open(path)?
+        .take(MAX_SOURCE_BYTES + 1)
+        .read_to_end(&mut source)?;
+    anyhow::ensure!(
+        source.len() as u64 <= MAX_SOURCE_BYTES,
+        "檔案超過 {} KB，不解析符號",
+        MAX_SOURCE_BYTES / 1024
+    );
+
+    let mut parser = Parser::new();
+    parser.set_language(&lang.language())?;
+    let Some(tree) = parser.parse(&source, None) else {
+        return Ok(Vec::new());
+    };
+
+    let mut symbols = Vec::new();
+    // 以明確的堆疊走訪，避免深層巢狀時遞迴過深
+    let mut stack: Vec<(Node, Option<String>)> = vec![(tree.root_node(), None)];
+    while let Some((node, container)) = stack.pop() {
+        if let Some(kind) = lang.symbol_kind(node) {
+            if let Some(name) = node
+                .child_by_field_name("name")
+                .and_then(|n| n.utf8_text(&source).ok())
+            {
+                symbols.push(Symbol {
+                    name: name.to_string(),
+                    kind: kind.to_string(),
+                    container: container.clone(),
+                    path: path.to_string_lossy().to_string(),
+                    relative: relative.to_string(),
+                    lines: LineRange {
+                        start: node.start_position().row + 1,
+                        end: node.end_position().row + 1,
+                    },
+                });
+            }
+        }
+
+        let child_container = lang.container_name(node, &source).or(container);
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.named_children(&mut cursor).collect();
+        // 反向放入堆疊，讓符號維持原始碼順序
+        for child in children.into_iter().rev() {
+            stack.push((child, child_container.clone()));
+        }
+    }
+
+    Ok(symbols)
+}
+
+struct CachedFile {
+    modified: SystemTime,
+    symbols: Arc<Vec<Symbol>>,
+}
+
+/// 需要（重新）解析的檔案
+struct PendingFile {
+    path: PathBuf,
+    relative: String,
+    modified: SystemTime,
+}
+
+/// 符號搜尋結果
+#[derive(Debug, Clone, Default)]
+pub struct SymbolSearchResult {
+    pub matches: Vec<SymbolMatch>,
+    /// 背景仍在解析部分檔案，結果可能不完整
+    pub indexing: bool,
+}
+
+/// 專案的符號索引，依檔案修改時間增量更新
+///
+/// 新增或修改的檔案在背景執行緒解析，搜尋不等待解析完成，先回傳已索引的結果。
+#[derive(Clone, Default)]
+pub struct SymbolIndex {
+    files: Arc<Mutex<HashMap<PathBuf, CachedFile>>>,
+    /// 正在背景更新的專案根目錄
+    updating: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl SymbolIndex {
+    /// 取得 `listing` 中已索引的符號，並在背景解析有變動的檔案
+    ///
+    /// 回傳的 bool 代表是否仍有檔案等待解析。已不在列表中的檔案（刪除或被忽略）會移出索引。
+    pub fn symbols(&self, listing: &Listing) -> (Vec<Arc<Vec<Symbol>>>, bool) {
+        let mut result = Vec::new();
+        let mut pending = Vec::new();
+        let mut listed = HashSet::new();
+
+        for entry in &listing.entries {
+            if entry.is_directory {
+                continue;
+            }
+            let path = PathBuf::from(&entry.path);
+            if Lang::from_path(&path).is_none() {
+                continue;
+            }
+            let Ok(metadata) = std::fs::metadata(&path) else {
+                continue;
+            };
+            if metadata.len() > MAX_SOURCE_BYTES {
+                continue;
+            }
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            listed.insert(path.clone());
+
+            match lock(&self.files).get(&path) {
+                Some(cached) if cached.modified == modified => {
+                    result.push(cached.symbols.clone());
+                    continue;
+                }
+                // 修改過的檔案在重新解析前仍回傳舊的符號
+                Some(cached) => result.push(cached.symbols.clone()),
+                None => {}
+            }
+            pending.push(PendingFile {
+                path,
+                relative: entry.relative.clone(),
+                modified,
+            });
+        }
+
+        lock(&self.files)
+            .retain(|path, _| !path.starts_with(&listing.root) || listed.contains(path));
+
+        let indexing = !pending.is_empty() || lock(&self.updating).contains(&listing.root);
+        if !pending.is_empty() {
+            self.update_in_background(listing.root.clone(), pending);
+        }
+        (result, indexing)
+    }
+
+    /// 在背景解析檔案，同一個專案同時只有一個更新執行緒
+    fn update_in_background(&self, root: PathBuf, pending: Vec<PendingFile>) {
+        if !lock(&self.updating).insert(root.clone()) {
+            return;
+        }
+
+        let index = self.clone();
+        let spawned = std::thread::Builder::new()
+            .name("symbol-index".into())
+            .spawn({
+                let root = root.clone();
+                move || {
+                    for file in pending {
+                        // 解析失敗也記錄下來，避免每次搜尋都重試
+                        let symbols = parse_symbols(&file.path, &file.relative)
+                            .inspect_err(|e| {
+                                log::debug!("解析 {} 的符號失敗: {}", file.path.display(), e)
+                            })
+                            .unwrap_or_default();
+                        lock(&index.files).insert(
+                            file.path,
+                            CachedFile {
+                                modified: file.modified,
+                                symbols: Arc::new(symbols),
+                            },
+                        );
+                    }
+                    lock(&index.updating).remove(&root);
+                }
+            });
+        if let Err(e) = spawned {
+            log::warn!("無法啟動符號索引執行緒: {}", e);
+            lock(&self.updating).remove(&root);
+        }
+    }
+
+    /// 以模糊比對搜尋符號名稱，依分數排序
+    pub fn search(&self, listing: &Listing, query: &str, limit: usize) -> SymbolSearchResult {
+        let pattern = Pattern::parse(query, CaseMatching::Smart, Normalization::Smart);
+        let mut matcher = Matcher::new(nucleo_matcher::Config::DEFAULT);
+        let mut buf = Vec::new();
+
+        let (symbols, indexing) = self.symbols(listing);
+        let mut matches: Vec<SymbolMatch> = symbols
+            .iter()
+            .flat_map(|symbols| symbols.iter())
+            .filter_map(|symbol| {
+                let haystack = Utf32Str::new(&symbol.name, &mut buf);
+                let mut indices = Vec::new();
+                let score = pattern.indices(haystack, &mut matcher, &mut indices)?;
+                indices.sort_unstable();
+                indices.dedup();
+                Some(SymbolMatch {
+                    symbol: symbol.clone(),
+                    score,
+                    indices,
+                })
+            })
+            .collect();
+
+        // 分數相同時，較短的名稱與較淺的路徑優先
+        matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.symbol.name.len().cmp(&b.symbol.name.len()))
+                .then_with(|| a.symbol.relative.len().cmp(&b.symbol.relative.len()))
+        });
+        matches.truncate(limit);
+        SymbolSearchResult { matches, indexing }
+    }
+
+    /// 確認用戶選擇的符號仍在輸入中被引用且位於專案內，並更新為目前的行範圍
+    pub fn verify(&self, root: &Path, input: &str, chosen: Vec<Symbol>) -> Vec<Symbol> {
+        let mut verified: Vec<Symbol> = Vec::new();
+
+        for symbol in chosen {
+            if !is_referenced(input, &symbol.name) {
+                continue;
+            }
+            let Ok(path) = resolve_within(root, Path::new(&symbol.path)) else {
+                log::debug!("符號 {} 不在專案目錄內，略過", symbol.name);
+                continue;
+            };
+
+            // 檔案在選擇後可能被修改，重新解析取得最新位置
+            let current = parse_symbols(&path, &symbol.relative)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|s| {
+                    s.name == symbol.name && s.kind == symbol.kind && s.container == symbol.container
+                })
+                .min_by_key(|s| s.lines.start.abs_diff(symbol.lines.start));
+            match current {
+                Some(current) if !verified.contains(&current) => verified.push(current),
+                Some(_) => {}
+                None => log::debug!("{} 中已找不到符號 {}", symbol.relative, symbol.name),
+            }
+        }
+
+        verified
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 輸入中是否有 `#name`（前面是開頭或空白，後面不是識別字字元）
+fn is_referenced(input: &str, name: &str) -> bool {
+    let needle = format!("#{}", name);
+    input.match_indices(&needle).any(|(start, _)| {
+        let before_ok = input[..start].chars().next_back().is_none_or(char::is_whitespace);
+        let after_ok = input[start + needle.len()..]
+            .chars()
+            .next()
+            .is_none_or(|c| !(c.is_alphanumeric() || c == '_'));
+        before_ok && after_ok
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 暫存的專案目錄，結束時刪除
+    struct Project(PathBuf);
+
+    impl Project {
+        fn new() -> Self {
+            let dir = std::env::temp_dir()
+                .join(format!("claude-confirm-symbols-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn write(&self, relative: &str, source: &str) -> PathBuf {
+            let path = self.0.join(relative);
+            std::fs::write(&path, source).unwrap();
+            path
+        }
+
+        /// 解析檔案，回傳（名稱, 種類, container）
+        fn parse(&self, relative: &str, source: &str) -> Vec<(String, String, Option<String>)> {
+            let path = self.write(relative, source);
+            parse_symbols(&path, relative)
+                .unwrap()
+                .into_iter()
+                .map(|s| (s.name, s.kind, s.container))
+                .collect()
+        }
+    }
+
+    impl Drop for Project {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn symbol(name: &str, kind: &str, container: Option<&str>) -> (String, String, Option<String>) {
+        (name.into(), kind.into(), container.map(String::from))
+    }
+
+    #[test]
+    fn rust_impl_methods_belong_to_the_type() {
+        let project = Project::new();
+        let source = "\
+struct Server;
+
+impl Server {
+    fn new() -> Self { Server }
+    pub fn run(&self) {}
+}
+
+fn main() {}
+";
+
+        assert_eq!(
+            project.parse("main.rs", source),
+            [
+                symbol("Server", "struct", None),
+                symbol("new", "method", Some("Server")),
+                symbol("run", "method", Some("Server")),
+                symbol("main", "function", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn typescript_arrow_functions_are_functions() {
+        let project = Project::new();
+        let source = "\
+export const add = (a: number, b: number) => a + b;
+const label = 'x';
+
+class Greeter {
+  greet() {}
+}
+";
+
+        assert_eq!(
+            project.parse("util.ts", source),
+            [
+                symbol("add", "function", None),
+                symbol("Greeter", "class", None),
+                symbol("greet", "method", Some("Greeter")),
+            ]
+        );
+    }
+
+    #[test]
+    fn python_decorated_methods_stay_methods() {
+        let project = Project::new();
+        let source = "\
+class Service:
+    @staticmethod
+    def create():
+        pass
+
+    @property
+    def name(self):
+        return 1
+
+@app.route('/')
+def index():
+    pass
+";
+
+        assert_eq!(
+            project.parse("app.py", source),
+            [
+                symbol("Service", "class", None),
+                symbol("create", "method", Some("Service")),
+                symbol("name", "method", Some("Service")),
+                symbol("index", "function", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn verify_skips_files_over_the_size_limit() {
+        let project = Project::new();
+        let small = project.write("small.rs", "fn small() {}\n");
+        let padding = "//".repeat(MAX_SOURCE_BYTES as usize / 2);
+        let large = project.write("large.rs", &format!("fn large() {{}}\n{}\n", padding));
+
+        let chosen = |name: &str, path: &Path| Symbol {
+            name: name.into(),
+            kind: "function".into(),
+            container: None,
+            path: path.to_string_lossy().to_string(),
+            relative: path.file_name().unwrap().to_string_lossy().to_string(),
+            lines: LineRange { start: 1, end: 1 },
+        };
+        let verified = SymbolIndex::default().verify(
+            &project.0,
+            "看一下 #small 和 #large",
+            vec![chosen("small", &small), chosen("large", &large)],
+        );
+
+        let names: Vec<&str> = verified.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["small"]);
+    }
+}
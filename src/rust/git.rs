@@ -0,0 +1,378 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// `git status` 補充資訊的時間上限，大型儲存庫超過時放棄，不拖慢彈窗
+const STATUS_TIMEOUT: Duration = Duration::from_millis(500);
+const STATUS_ARGS: &[&str] = &[
+    "status",
+    "--porcelain=v2",
+    "--branch",
+    "-z",
+    "--untracked-files=normal",
+];
+/// 查詢引用檔案狀態的時間上限
+const FILE_STATUS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Git 儲存庫狀態
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitInfo {
+    /// 儲存庫根目錄（工作樹頂層）
+    pub root: String,
+    /// 目前分支，detached HEAD 時為 None
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// HEAD commit 短 hash
+    #[serde(default)]
+    pub head: Option<String>,
+    /// 有變更（含未追蹤）的檔案數
+    #[serde(default)]
+    pub dirty_files: Option<usize>,
+    /// 領先 upstream 的 commit 數
+    #[serde(default)]
+    pub ahead: Option<u32>,
+    /// 落後 upstream 的 commit 數
+    #[serde(default)]
+    pub behind: Option<u32>,
+}
+
+impl GitInfo {
+    /// 偵測 `cwd` 所在的 git 儲存庫，不在儲存庫內時回傳 None
+    ///
+    /// 根目錄、分支與 HEAD 直接讀取 `.git` 內的檔案；變更檔案數與 ahead/behind
+    /// 需要比對 index 與 commit graph，改用一次 `git status`，git 不可用或逾時時留空。
+    pub fn detect(cwd: &Path) -> Option<Self> {
+        let mut info = Self::detect_head(cwd)?;
+        let root = PathBuf::from(&info.root);
+        info.read_status(&root);
+        Some(info)
+    }
+
+    /// 只讀取 `.git` 內的檔案（根目錄、分支與 HEAD），不執行 git
+    pub fn detect_head(cwd: &Path) -> Option<Self> {
+        let (root, git_dir) = find_repository(cwd)?;
+        let common_dir = common_dir(&git_dir);
+
+        let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+        let head = head.trim();
+
+        let (branch, hash) = match head.strip_prefix("ref: ") {
+            Some(reference) => (
+                reference.strip_prefix("refs/heads/").map(String::from),
+                resolve_ref(&git_dir, &common_dir, reference),
+            ),
+            None => (None, Some(head.to_string())),
+        };
+
+        Some(Self {
+            root: root.to_string_lossy().to_string(),
+            branch,
+            head: hash.map(|h| h.chars().take(7).collect()),
+            ..Default::default()
+        })
+    }
+
+    /// 以 `git status --porcelain=v2 --branch -z` 補上變更檔案數與 ahead/behind
+    fn read_status(&mut self, root: &Path) {
+        let Some(output) = run_git(root, STATUS_ARGS, STATUS_TIMEOUT) else {
+            return;
+        };
+
+        let summary = parse_status(&output);
+        self.dirty_files = Some(summary.dirty_files);
+        self.ahead = summary.ahead;
+        self.behind = summary.behind;
+    }
+}
+
+/// `git status --porcelain=v2 --branch -z` 的摘要
+#[derive(Debug, Default, PartialEq, Eq)]
+struct StatusSummary {
+    dirty_files: usize,
+    ahead: Option<u32>,
+    behind: Option<u32>,
+}
+
+/// 解析 `git status --porcelain=v2 --branch -z` 的輸出
+///
+/// 每筆以 `\0` 結尾；`# ` 開頭為標頭，改名或複製（`2 ` 開頭）的紀錄後面多一筆原路徑。
+fn parse_status(output: &[u8]) -> StatusSummary {
+    let stdout = String::from_utf8_lossy(output);
+    let mut summary = StatusSummary::default();
+    let mut records = stdout.split('\0');
+    while let Some(record) = records.next() {
+        if let Some(ab) = record.strip_prefix("# branch.ab ") {
+            // 格式: +<ahead> -<behind>
+            let mut parts = ab.split_whitespace();
+            summary.ahead = parts.next().and_then(|a| a.trim_start_matches('+').parse().ok());
+            summary.behind = parts.next().and_then(|b| b.trim_start_matches('-').parse().ok());
+        } else if !record.starts_with('#') && !record.is_empty() {
+            if record.starts_with("2 ") {
+                records.next();
+            }
+            summary.dirty_files += 1;
+        }
+    }
+    summary
+}
+
+/// 查詢檔案的 git 狀態，回傳絕對路徑對應 porcelain 的 XY 代碼（例如 ` M`、`??`）
+///
+/// 未變更、被忽略或不在儲存庫內的檔案不會出現在結果中。
+pub fn file_statuses(cwd: &Path, paths: &[PathBuf]) -> HashMap<PathBuf, String> {
+    let mut statuses = HashMap::new();
+    let Some((root, _)) = find_repository(cwd) else {
+        return statuses;
+    };
+    if paths.is_empty() {
+        return statuses;
+    }
+
+    let options = ["status", "--porcelain=v1", "-z", "--untracked-files=all", "--"];
+    let args: Vec<&OsStr> = options
+        .iter()
+        .map(OsStr::new)
+        .chain(paths.iter().map(|path| path.as_os_str()))
+        .collect();
+    let Some(output) = run_git(&root, &args, FILE_STATUS_TIMEOUT) else {
+        return statuses;
+    };
+
+    // 每筆為 "XY <path>\0"，改名時後面多一筆原路徑
+    let stdout = String::from_utf8_lossy(&output);
+    let mut records = stdout.split('\0');
+    while let Some(record) = records.next() {
+        if record.len() < 4 {
+            continue;
+        }
+        let (code, path) = record.split_at(2);
+        if code.contains('R') || code.contains('C') {
+            records.next();
+        }
+        statuses.insert(root.join(&path[1..]), code.to_string());
+    }
+    statuses
+}
+
+/// 在 `root` 執行 git，成功時回傳 stdout；超過 `timeout` 時終止並回傳 None
+///
+/// 加上 `--no-optional-locks`（與 `GIT_OPTIONAL_LOCKS=0`），`git status` 不會為了更新 index
+/// 而取得 `index.lock`，避免與 agent 同時執行的 git 指令衝突。
+fn run_git<S: AsRef<OsStr>>(root: &Path, args: &[S], timeout: Duration) -> Option<Vec<u8>> {
+    let mut child = Command::new("git")
+        .arg("--no-optional-locks")
+        .arg("-C")
+        .arg(root)
+        .args(args)
+        .env("GIT_OPTIONAL_LOCKS", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    // 另開執行緒讀取 stdout，輸出超過 pipe 緩衝區時 git 才不會卡住
+    let mut stdout = child.stdout.take()?;
+    let reader = std::thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stdout.read_to_end(&mut output);
+        output
+    });
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                let output = reader.join().ok()?;
+                return status.success().then_some(output);
+            }
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(10)),
+            _ => {
+                log::debug!("git 在 {:?} 內沒有完成，略過: {}", timeout, root.display());
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    }
+}
+
+/// 往上尋找 `.git`，回傳（工作樹根目錄, git 目錄）
+fn find_repository(cwd: &Path) -> Option<(PathBuf, PathBuf)> {
+    for dir in cwd.ancestors() {
+        let dot_git = dir.join(".git");
+        if dot_git.is_dir() {
+            return Some((dir.to_path_buf(), dot_git));
+        }
+        // worktree / submodule：.git 是內容為 `gitdir: <path>` 的檔案
+        if dot_git.is_file() {
+            let content = std::fs::read_to_string(&dot_git).ok()?;
+            let git_dir = content.trim().strip_prefix("gitdir: ")?;
+            return Some((dir.to_path_buf(), dir.join(git_dir)));
+        }
+    }
+    None
+}
+
+/// worktree 的 refs 與 packed-refs 放在共用的 git 目錄
+fn common_dir(git_dir: &Path) -> PathBuf {
+    std::fs::read_to_string(git_dir.join("commondir"))
+        .map(|dir| git_dir.join(dir.trim()))
+        .unwrap_or_else(|_| git_dir.to_path_buf())
+}
+
+/// 解析 ref 到 commit hash（loose ref 優先，其次 packed-refs）
+fn resolve_ref(git_dir: &Path, common_dir: &Path, reference: &str) -> Option<String> {
+    for dir in [git_dir, common_dir] {
+        if let Ok(hash) = std::fs::read_to_string(dir.join(reference)) {
+            return Some(hash.trim().to_string());
+        }
+    }
+
+    let packed = std::fs::read_to_string(common_dir.join("packed-refs")).ok()?;
+    packed
+        .lines()
+        .filter(|line| !line.starts_with('#') && !line.starts_with('^'))
+        .find_map(|line| {
+            let (hash, name) = line.split_once(' ')?;
+            (name == reference).then(|| hash.to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "0123456789abcdef0123456789abcdef01234567";
+    const PACKED_HASH: &str = "fedcba9876543210fedcba9876543210fedcba98";
+
+    /// 以檔案組成的假儲存庫，結束時刪除
+    struct Fixture(PathBuf);
+
+    impl Fixture {
+        fn new() -> Self {
+            let dir = std::env::temp_dir()
+                .join(format!("claude-confirm-git-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn write(&self, path: &str, content: &str) -> &Self {
+            let path = self.0.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+            self
+        }
+
+        fn detect(&self, cwd: &str) -> GitInfo {
+            let cwd = self.0.join(cwd);
+            std::fs::create_dir_all(&cwd).unwrap();
+            GitInfo::detect_head(&cwd).unwrap()
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn branch_head_resolves_loose_ref_from_a_subdirectory() {
+        let repo = Fixture::new();
+        repo.write(".git/HEAD", "ref: refs/heads/main\n")
+            .write(".git/refs/heads/main", &format!("{}\n", HASH));
+
+        let info = repo.detect("src/nested");
+        assert_eq!(info.root, repo.0.to_string_lossy());
+        assert_eq!(info.branch.as_deref(), Some("main"));
+        assert_eq!(info.head.as_deref(), Some("0123456"));
+    }
+
+    #[test]
+    fn detached_head_has_no_branch() {
+        let repo = Fixture::new();
+        repo.write(".git/HEAD", &format!("{}\n", HASH));
+
+        let info = repo.detect("");
+        assert_eq!(info.branch, None);
+        assert_eq!(info.head.as_deref(), Some("0123456"));
+    }
+
+    #[test]
+    fn packed_refs_are_used_when_there_is_no_loose_ref() {
+        let repo = Fixture::new();
+        repo.write(".git/HEAD", "ref: refs/heads/release/1.0\n").write(
+            ".git/packed-refs",
+            &format!(
+                "# pack-refs with: peeled fully-peeled sorted\n\
+                 {} refs/heads/main\n\
+                 {} refs/heads/release/1.0\n\
+                 ^{}\n",
+                HASH, PACKED_HASH, HASH
+            ),
+        );
+
+        let info = repo.detect("");
+        assert_eq!(info.branch.as_deref(), Some("release/1.0"));
+        assert_eq!(info.head.as_deref(), Some("fedcba9"));
+    }
+
+    #[test]
+    fn loose_ref_wins_over_packed_refs() {
+        let repo = Fixture::new();
+        repo.write(".git/HEAD", "ref: refs/heads/main\n")
+            .write(".git/refs/heads/main", HASH)
+            .write(".git/packed-refs", &format!("{} refs/heads/main\n", PACKED_HASH));
+
+        assert_eq!(repo.detect("").head.as_deref(), Some("0123456"));
+    }
+
+    #[test]
+    fn worktree_reads_head_from_gitdir_and_refs_from_commondir() {
+        let repo = Fixture::new();
+        repo.write("main/.git/HEAD", "ref: refs/heads/main\n")
+            .write("main/.git/packed-refs", &format!("{} refs/heads/feature\n", PACKED_HASH))
+            .write("main/.git/worktrees/wt/HEAD", "ref: refs/heads/feature\n")
+            .write("main/.git/worktrees/wt/commondir", "../..\n")
+            .write("wt/.git", "gitdir: ../main/.git/worktrees/wt\n");
+
+        let info = repo.detect("wt/src");
+        assert_eq!(info.root, repo.0.join("wt").to_string_lossy());
+        assert_eq!(info.branch.as_deref(), Some("feature"));
+        assert_eq!(info.head.as_deref(), Some("fedcba9"));
+
+        repo.write("main/.git/refs/heads/feature", HASH);
+        assert_eq!(repo.detect("wt").head.as_deref(), Some("0123456"));
+    }
+
+    #[test]
+    fn status_counts_records_and_reads_ahead_behind() {
+        let output = "# branch.oid 0123456789abcdef0123456789abcdef01234567\0\
+                      # branch.head main\0\
+                      # branch.upstream origin/main\0\
+                      # branch.ab +2 -1\0\
+                      1 .M N... 100644 100644 100644 aaaa bbbb src/main.rs\0\
+                      2 R. N... 100644 100644 100644 aaaa bbbb R100 new name.rs\0old name.rs\0\
+                      ? untracked.txt\0";
+
+        assert_eq!(
+            parse_status(output.as_bytes()),
+            StatusSummary {
+                dirty_files: 3,
+                ahead: Some(2),
+                behind: Some(1),
+            }
+        );
+    }
+
+    #[test]
+    fn status_without_upstream_has_no_ahead_behind() {
+        let output = "# branch.oid (initial)\0# branch.head main\0";
+        assert_eq!(parse_status(output.as_bytes()), StatusSummary::default());
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::types::{PopupRequest, UserResponse};
+
+/// 保留的歷史紀錄上限，超過時刪除最舊的
+const MAX_HISTORY_ENTRIES: usize = 500;
+
+/// 一次完成的確認互動
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub created_at: DateTime<Local>,
+    pub answered_at: DateTime<Local>,
+    pub request: PopupRequest,
+    pub response: UserResponse,
+}
+
+/// 歷史紀錄目錄（`<data_local_dir>/claude-confirm/history`）
+pub fn history_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("claude-confirm").join("history"))
+}
+
+/// 寫入一筆歷史紀錄，並清理超出上限的舊紀錄
+pub fn record(entry: &HistoryEntry) -> Result<PathBuf> {
+    let dir = history_dir().context("無法取得歷史紀錄目錄")?;
+    std::fs::create_dir_all(&dir)?;
+
+    let path = dir.join(format!("{}.json", entry.id));
+    std::fs::write(&path, serde_json::to_string_pretty(entry)?)?;
+
+    prune(MAX_HISTORY_ENTRIES);
+    Ok(path)
+}
+
+/// 依 ID 讀取歷史紀錄，也接受 ID 前綴
+pub fn load(id: &str) -> Result<HistoryEntry> {
+    let dir = history_dir().context("無法取得歷史紀錄目錄")?;
+
+    let exact = dir.join(format!("{}.json", id));
+    let path = if exact.exists() {
+        exact
+    } else {
+        let matches: Vec<PathBuf> = entry_paths()
+            .into_iter()
+            .filter(|path| {
+                path.file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .is_some_and(|stem| stem.starts_with(id))
+            })
+            .collect();
+        match matches.as_slice() {
+            [only] => only.clone(),
+            [] => anyhow::bail!("找不到歷史紀錄: {}", id),
+            _ => anyhow::bail!("ID 前綴 {} 符合多筆紀錄，請提供更長的 ID", id),
+        }
+    };
+
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("讀取歷史紀錄失敗: {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("解析歷史紀錄失敗: {}", path.display()))
+}
+
+/// 列出歷史紀錄，新的在前
+pub fn list() -> Vec<HistoryEntry> {
+    let mut entries: Vec<HistoryEntry> = entry_paths()
+        .into_iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .filter_map(|content| serde_json::from_str(&content).ok())
+        .collect();
+    entries.sort_by(|a, b| b.answered_at.cmp(&a.answered_at));
+    entries
+}
+
+fn entry_paths() -> Vec<PathBuf> {
+    let Some(dir) = history_dir() else {
+        return Vec::new();
+    };
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 只保留最新的 `keep` 筆（依修改時間）
+fn prune(keep: usize) {
+    let mut paths: Vec<(std::time::SystemTime, PathBuf)> = entry_paths()
+        .into_iter()
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((modified, path))
+        })
+        .collect();
+    if paths.len() <= keep {
+        return;
+    }
+
+    paths.sort_by(|a, b| b.0.cmp(&a.0));
+    for (_, path) in paths.into_iter().skip(keep) {
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -1,5 +1,15 @@
+pub mod cli;
+pub mod config;
+pub mod export;
+pub mod files;
+pub mod git;
+pub mod history;
 pub mod mcp;
+pub mod popup;
+pub mod session;
+pub mod tool_use;
 pub mod types;
 pub mod ui;
+pub mod update;
 
 pub use types::*;
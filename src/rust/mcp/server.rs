@@ -5,15 +5,83 @@ use rmcp::{
     transport::stdio,
     service::RequestContext,
 };
-use std::sync::Arc;
-use std::process::Command;
-use std::fs;
+use std::sync::{Arc, Mutex};
 
-use super::types::ConfirmRequest;
-use crate::types::{PopupRequest, UserResponse};
+use super::permissions::{self, AllowRule};
+use super::types::{ApproveToolUseRequest, ConfirmRequest};
+use crate::config::{ClientProfile, Config};
+use crate::files::format_size;
+use crate::popup::{show_popup, show_tool_use_popup};
+use crate::tool_use;
+use crate::types::{
+    Attachment, AttachmentContent, EnvContext, McpClientInfo, Mention, PopupRequest, Section,
+    Symbol,
+};
+
+/// 預設的 MCP instructions
+const DEFAULT_INSTRUCTIONS: &str = "Claude Confirm - 互動確認工具\n\n\
+⚠️ 重要：在完成以下任何操作後，請主動調用 confirm 工具：\n\
+- 完成多步驟任務\n\
+- 修改多個文件\n\
+- 完成重要的配置更改\n\
+- 執行構建或測試\n\
+- 完成問題診斷和修復\n\
+- 完成代碼重構\n\
+- 總結工作成果時\n\n\
+使用方式：\n\
+1. 將工作成果整理成 Markdown 格式\n\
+2. 將相關內容分段（sections）讓用戶選擇\n\
+3. 調用 confirm 工具展示給用戶\n\
+4. 獲取用戶的確認、選擇和額外輸入\n\n\
+⚠️ sections 使用規範（必讀！）：\n\
+sections 的正確用途是提供「後續可選的改進項目」，而非已完成的項目。\n\n\
+**正確做法**：\n\
+- message: 總結已完成的工作（✅ 已完成 A、B、C）\n\
+- sections: 列出可選的後續任務（例如：添加測試、優化性能、修復安全問題）\n\
+- 每個 section 必須是明確、可立即執行的任務\n\n\
+**錯誤做法**：\n\
+- ❌ sections 放已完成的項目\n\
+- ❌ sections 內容模糊不清（如「可能需要...」）\n\
+- ❌ sections 是討論性問題而非可執行任務\n\n\
+範例（正確）：\n\
+message: '✅ 已完成功能 A 和 B\\n\\n以下是後續可選改進：'\n\
+sections: [\n\
+  {title: '🔴 修復 XSS 漏洞', content: '在 MarkdownViewer 添加 DOMPurify', selected: false},\n\
+  {title: '🟠 添加 TypeScript', content: '將 .js 改為 .ts 並添加類型', selected: false}\n\
+]\n\n\
+⚠️ 返回值處理規則（重要！）：\n\
+當用戶確認後，你會收到以下格式的返回：\n\
+- '選中的段落: [index_array]' - 用戶最終選擇的段落索引數組（從 0 開始）\n\
+- '用戶輸入: string' - 用戶的額外文字輸入\n\n\
+**必須嚴格遵守**：\n\
+1. 只處理 index_array 中的項目，不要添加用戶未選擇的項目\n\
+2. 不要按照「你認為重要的優先級」自行決定任務\n\
+3. 用戶選擇後，立即執行該任務，不要再詢問「要實作還是給建議」\n\
+4. 結合用戶輸入來理解額外需求\n\n\
+範例：\n\
+假設你發送了 5 個 sections（索引 0-4），用戶只選了 [1, 3]：\n\
+→ ✅ 正確：立即開始執行索引 1 和 3 的任務\n\
+→ ❌ 錯誤：詢問用戶「要不要做」或「怎麼做」\n\
+→ ❌ 錯誤：處理索引 0、2、4 或其他未選項目\n\n\
+範例：完成任務後調用 confirm，總結修改的文件、解決的問題、測試結果等。";
+
+/// confirm 工具的預設描述
+const CONFIRM_TOOL_DESCRIPTION: &str = "⚠️ 在完成任務、修改文件、構建測試後主動調用此工具。\n\
+    用於：展示 Markdown 格式的工作總結、讓用戶選擇相關段落、獲取確認和額外輸入。\n\
+    自動觸發時機：多步驟任務完成、重要更改完成、問題解決後、代碼重構後。";
 
-#[derive(Clone)]
-pub struct ConfirmServer;
+/// approve_tool_use 工具的預設描述
+const APPROVE_TOOL_DESCRIPTION: &str = "工具權限確認（供 Claude Code --permission-prompt-tool 使用）。\n\
+    以視窗顯示工具名稱與參數，回傳 {\"behavior\":\"allow\",\"updatedInput\":{...}} \
+    或 {\"behavior\":\"deny\",\"message\":\"...\"}。";
+
+#[derive(Clone, Default)]
+pub struct ConfirmServer {
+    /// 本次 session（服務器進程生命週期內）的「一律允許」規則
+    session_rules: Arc<Mutex<Vec<AllowRule>>>,
+    /// initialize 時取得的 MCP 客戶端資訊
+    client: Arc<Mutex<Option<McpClientInfo>>>,
+}
 
 impl ServerHandler for ConfirmServer {
     fn get_info(&self) -> ServerInfo {
@@ -28,59 +96,28 @@ impl ServerHandler for ConfirmServer {
         )
         .with_server_info(server_impl)
         .with_instructions(
-            "Claude Confirm - 互動確認工具\n\n\
-            ⚠️ 重要：在完成以下任何操作後，請主動調用 confirm 工具：\n\
-            - 完成多步驟任務\n\
-            - 修改多個文件\n\
-            - 完成重要的配置更改\n\
-            - 執行構建或測試\n\
-            - 完成問題診斷和修復\n\
-            - 完成代碼重構\n\
-            - 總結工作成果時\n\n\
-            使用方式：\n\
-            1. 將工作成果整理成 Markdown 格式\n\
-            2. 將相關內容分段（sections）讓用戶選擇\n\
-            3. 調用 confirm 工具展示給用戶\n\
-            4. 獲取用戶的確認、選擇和額外輸入\n\n\
-            ⚠️ sections 使用規範（必讀！）：\n\
-            sections 的正確用途是提供「後續可選的改進項目」，而非已完成的項目。\n\n\
-            **正確做法**：\n\
-            - message: 總結已完成的工作（✅ 已完成 A、B、C）\n\
-            - sections: 列出可選的後續任務（例如：添加測試、優化性能、修復安全問題）\n\
-            - 每個 section 必須是明確、可立即執行的任務\n\n\
-            **錯誤做法**：\n\
-            - ❌ sections 放已完成的項目\n\
-            - ❌ sections 內容模糊不清（如「可能需要...」）\n\
-            - ❌ sections 是討論性問題而非可執行任務\n\n\
-            範例（正確）：\n\
-            message: '✅ 已完成功能 A 和 B\\n\\n以下是後續可選改進：'\n\
-            sections: [\n\
-              {title: '🔴 修復 XSS 漏洞', content: '在 MarkdownViewer 添加 DOMPurify', selected: false},\n\
-              {title: '🟠 添加 TypeScript', content: '將 .js 改為 .ts 並添加類型', selected: false}\n\
-            ]\n\n\
-            ⚠️ 返回值處理規則（重要！）：\n\
-            當用戶確認後，你會收到以下格式的返回：\n\
-            - '選中的段落: [index_array]' - 用戶最終選擇的段落索引數組（從 0 開始）\n\
-            - '用戶輸入: string' - 用戶的額外文字輸入\n\n\
-            **必須嚴格遵守**：\n\
-            1. 只處理 index_array 中的項目，不要添加用戶未選擇的項目\n\
-            2. 不要按照「你認為重要的優先級」自行決定任務\n\
-            3. 用戶選擇後，立即執行該任務，不要再詢問「要實作還是給建議」\n\
-            4. 結合用戶輸入來理解額外需求\n\n\
-            範例：\n\
-            假設你發送了 5 個 sections（索引 0-4），用戶只選了 [1, 3]：\n\
-            → ✅ 正確：立即開始執行索引 1 和 3 的任務\n\
-            → ❌ 錯誤：詢問用戶「要不要做」或「怎麼做」\n\
-            → ❌ 錯誤：處理索引 0、2、4 或其他未選項目\n\n\
-            範例：完成任務後調用 confirm，總結修改的文件、解決的問題、測試結果等。"
+            self.client_profile()
+                .and_then(|profile| profile.instructions)
+                .unwrap_or_else(|| DEFAULT_INSTRUCTIONS.to_string()),
         )
     }
 
     async fn initialize(
         &self,
-        _request: InitializeRequestParams,
+        request: InitializeRequestParams,
         _context: RequestContext<RoleServer>,
     ) -> Result<ServerInfo, McpError> {
+        let client = McpClientInfo {
+            name: request.client_info.name.clone(),
+            version: Some(request.client_info.version.clone()).filter(|v| !v.is_empty()),
+            title: request.client_info.title.clone(),
+        };
+        log::info!("MCP 客戶端: {} {}", client.name, client.version.as_deref().unwrap_or(""));
+
+        if let Ok(mut slot) = self.client.lock() {
+            *slot = Some(client);
+        }
+
         Ok(self.get_info())
     }
 
@@ -117,22 +154,46 @@ impl ServerHandler for ConfirmServer {
             "required": ["message"]
         });
 
-        let tool = if let serde_json::Value::Object(schema_map) = schema {
-            Tool::new(
-                "confirm",
-                "⚠️ 在完成任務、修改文件、構建測試後主動調用此工具。\n\
-                用於：展示 Markdown 格式的工作總結、讓用戶選擇相關段落、獲取確認和額外輸入。\n\
-                自動觸發時機：多步驟任務完成、重要更改完成、問題解決後、代碼重構後。",
-                Arc::new(schema_map),
-            )
-        } else {
-            return Err(McpError::internal_error(
-                "無法創建工具 schema".to_string(),
-                None
-            ));
+        let profile = self.client_profile();
+        let description = |name: &str, default: &str| {
+            profile
+                .as_ref()
+                .and_then(|p| p.tool_descriptions.get(name).cloned())
+                .unwrap_or_else(|| default.to_string())
         };
 
-        Ok(ListToolsResult::with_all_items(vec![tool]))
+        let confirm_tool = tool(
+            "confirm",
+            description("confirm", CONFIRM_TOOL_DESCRIPTION),
+            schema,
+        )?;
+
+        let approve_schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "tool_name": {
+                    "type": "string",
+                    "description": "請求權限的工具名稱"
+                },
+                "input": {
+                    "type": "object",
+                    "description": "工具的輸入參數"
+                },
+                "tool_use_id": {
+                    "type": "string",
+                    "description": "工具調用 ID"
+                }
+            },
+            "required": ["tool_name", "input"]
+        });
+
+        let approve_tool = tool(
+            "approve_tool_use",
+            description("approve_tool_use", APPROVE_TOOL_DESCRIPTION),
+            approve_schema,
+        )?;
+
+        Ok(ListToolsResult::with_all_items(vec![confirm_tool, approve_tool]))
     }
 
     async fn call_tool(
@@ -154,6 +215,19 @@ impl ServerHandler for ConfirmServer {
 
                 self.handle_confirm(confirm_request).await
             }
+            "approve_tool_use" => {
+                let arguments_value = request
+                    .arguments
+                    .map(serde_json::Value::Object)
+                    .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
+
+                let approve_request: ApproveToolUseRequest =
+                    serde_json::from_value(arguments_value).map_err(|e| {
+                        McpError::invalid_params(format!("參數解析失敗: {}", e), None)
+                    })?;
+
+                self.handle_approve_tool_use(approve_request).await
+            }
             _ => Err(McpError::invalid_request(
                 format!("未知的工具: {}", request.name),
                 None,
@@ -163,11 +237,32 @@ impl ServerHandler for ConfirmServer {
 }
 
 impl ConfirmServer {
+    fn client(&self) -> Option<McpClientInfo> {
+        self.client.lock().ok().and_then(|client| client.clone())
+    }
+
+    /// 目前客戶端對應的設定（instructions、工具描述）
+    fn client_profile(&self) -> Option<ClientProfile> {
+        let client = self.client()?;
+        Config::load().client_profile(&client.name).cloned()
+    }
+
+    /// 自動偵測環境資訊，並附上 MCP 客戶端資訊
+    fn env_context(&self) -> EnvContext {
+        EnvContext {
+            client: self.client(),
+            ..EnvContext::detect()
+        }
+    }
+
     async fn handle_confirm(&self, request: ConfirmRequest) -> Result<CallToolResult, McpError> {
-        let popup_request: PopupRequest = request.into();
+        let mut popup_request: PopupRequest = request.into();
+        if let Some(env_context) = popup_request.env_context.as_mut() {
+            env_context.client = self.client();
+        }
 
         // 調用 UI 程序
-        match self.create_ui_popup(&popup_request).await {
+        match show_popup(&popup_request, None).await {
             Ok(response) => {
                 // 構建回應
                 let mut content = if response.confirmed {
@@ -201,11 +296,52 @@ impl ConfirmServer {
                     content.push_str(&format!("\n\n💬 用戶額外要求：\n{}", response.user_input));
                 }
 
+                if !response.mentions.is_empty() {
+                    content.push_str("\n\n📎 用戶引用的檔案：\n");
+                    for mention in &response.mentions {
+                        content.push_str(&format!("- {}\n", mention_line(mention)));
+                        if let Some(selected) = &mention.content {
+                            let language = tool_use::language_of(Some(&mention.relative));
+                            content.push_str(&tool_use::fenced(&language, selected));
+                        }
+                    }
+                }
+
+                if !response.symbols.is_empty() {
+                    content.push_str("\n\n🔣 用戶引用的符號：\n");
+                    for symbol in &response.symbols {
+                        content.push_str(&format!("- {}\n", symbol_line(symbol)));
+                    }
+                }
+
+                // 圖片附件以 image content 回傳，其他內容放在文字中
+                let mut images = Vec::new();
+                if !response.attachments.is_empty() {
+                    content.push_str("\n\n📎 用戶附加的檔案：\n");
+                    for attachment in &response.attachments {
+                        content.push_str(&format!("- {}\n", attachment_line(attachment)));
+                        match &attachment.content {
+                            Some(AttachmentContent::Text(text)) => {
+                                let language = tool_use::language_of(Some(&attachment.name));
+                                content.push_str(&tool_use::fenced(&language, text));
+                            }
+                            Some(AttachmentContent::Base64(data))
+                                if attachment.mime.starts_with("image/") =>
+                            {
+                                images.push(Content::image(data.clone(), attachment.mime.clone()));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+
                 if !response.images.is_empty() {
                     content.push_str(&format!("\n\n附加圖片: {} 張", response.images.len()));
                 }
 
-                Ok(CallToolResult::success(vec![Content::text(content)]))
+                let mut result = vec![Content::text(content)];
+                result.extend(images);
+                Ok(CallToolResult::success(result))
             }
             Err(e) => {
                 Err(McpError::internal_error(
@@ -216,84 +352,201 @@ impl ConfirmServer {
         }
     }
 
-    async fn create_ui_popup(&self, request: &PopupRequest) -> Result<UserResponse> {
-        // 創建臨時文件
-        let temp_dir = std::env::temp_dir();
-        let temp_file = temp_dir.join(format!("mcp_request_{}.json", request.id));
+    async fn handle_approve_tool_use(
+        &self,
+        request: ApproveToolUseRequest,
+    ) -> Result<CallToolResult, McpError> {
+        let env_context = self.env_context();
+        let project_root = env_context.cwd.clone().map(std::path::PathBuf::from);
+        let tool_name = request.tool_name.as_str();
+
+        // 先比對 session 與專案的「一律允許」規則
+        let session_allowed = self
+            .session_rules
+            .lock()
+            .map(|rules| rules.iter().any(|r| r.matches(tool_name, &request.input)))
+            .unwrap_or(false);
+        let project_allowed = project_root.as_deref().is_some_and(|root| {
+            permissions::load_project_rules(root)
+                .iter()
+                .any(|r| r.matches(tool_name, &request.input))
+        });
+        if session_allowed || project_allowed {
+            log::info!("工具調用符合一律允許規則: {}", tool_name);
+            return Ok(permission_result(allow(request.input)));
+        }
+
+        log::info!("請求工具權限: {} ({:?})", tool_name, request.tool_use_id);
+        let rule = AllowRule::for_call(tool_name, &request.input);
+        let popup_request = PopupRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            message: tool_use::render_markdown(tool_name, &request.input),
+            sections: vec![
+                Section {
+                    title: "✏️ 修改後再執行".to_string(),
+                    content: tool_use::edit_hint(tool_name).to_string(),
+                    selected: false,
+                },
+                Section {
+                    title: format!("🔁 本次 session 一律允許 `{}`", rule.label()),
+                    content: "直到 MCP 服務器結束前，相同的工具調用不再詢問".to_string(),
+                    selected: false,
+                },
+                Section {
+                    title: format!("📌 此專案一律允許 `{}`", rule.label()),
+                    content: "記錄到用戶設定目錄的 claude-confirm/permissions.json（不會寫入專案）"
+                        .to_string(),
+                    selected: false,
+                },
+            ],
+            is_markdown: true,
+            env_context: Some(env_context),
+        };
 
-        let request_json = serde_json::to_string_pretty(request)?;
-        fs::write(&temp_file, request_json)?;
+        let response = show_tool_use_popup(&popup_request, None).await.map_err(|e| {
+            McpError::internal_error(format!("UI 互動失敗: {}", e), None)
+        })?;
 
-        // 查找 UI 命令
-        let ui_command = self.find_ui_command()?;
+        let note = response.user_input.trim();
 
-        // 調用 UI 程序
-        let output = Command::new(&ui_command)
-            .arg("--mcp-request")
-            .arg(&temp_file)
-            .output()?;
-
-        // 清理臨時文件
-        let _ = fs::remove_file(&temp_file);
-
-        if output.status.success() {
-            let response_text = String::from_utf8_lossy(&output.stdout);
-            let response: UserResponse = serde_json::from_str(response_text.trim())
-                .unwrap_or_else(|_| UserResponse::cancelled());
-            Ok(response)
-        } else {
-            let error = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("UI 程序失敗: {}", error);
+        if !response.confirmed {
+            let message = if note.is_empty() {
+                "用戶拒絕了此工具調用".to_string()
+            } else {
+                format!("用戶拒絕了此工具調用：{}", note)
+            };
+            return Ok(permission_result(serde_json::json!({
+                "behavior": "deny",
+                "message": message,
+            })));
         }
-    }
 
-    fn find_ui_command(&self) -> Result<String> {
-        // macOS: 優先使用 .app bundle 內的 binary（需要 Info.plist 才能啟動 WebView）
-        let app_bundle_paths = [
-            "/Applications/claude-confirm.app/Contents/MacOS/claude-confirm-ui",
-            // homebrew cask 或自訂安裝位置
-            &format!("{}/Applications/claude-confirm.app/Contents/MacOS/claude-confirm-ui",
-                dirs::home_dir().map(|h| h.to_string_lossy().to_string()).unwrap_or_default()),
-        ];
-        for path in &app_bundle_paths {
-            let p = std::path::Path::new(path);
-            if p.exists() {
-                return Ok(path.to_string());
-            }
+        let selected = |idx: usize| response.selected_sections.contains(&idx);
+
+        // allow 的回應無法附帶訊息；輸入了內容卻沒有勾選修改時，不能默默丟掉內容照原參數執行
+        if !selected(0) && !note.is_empty() {
+            return Ok(permission_result(serde_json::json!({
+                "behavior": "deny",
+                "message": format!(
+                    "用戶確認時附上了說明但沒有選擇修改參數，本次未執行，請依說明調整後重新調用：{}",
+                    note
+                ),
+            })));
         }
 
-        // 查找同目錄的 .app bundle
-        if let Ok(current_exe) = std::env::current_exe() {
-            if let Some(exe_dir) = current_exe.parent() {
-                // 同目錄的 .app bundle
-                let bundle_ui = exe_dir.join("bundle/macos/claude-confirm.app/Contents/MacOS/claude-confirm-ui");
-                if bundle_ui.exists() {
-                    return Ok(bundle_ui.to_string_lossy().to_string());
+        let updated_input = if selected(0) && !note.is_empty() {
+            match tool_use::edited_input(tool_name, &request.input, note) {
+                Ok(updated) => updated,
+                Err(e) => {
+                    return Ok(permission_result(serde_json::json!({
+                        "behavior": "deny",
+                        "message": format!("用戶修改的參數無效: {}", e),
+                    })));
                 }
-                // 同目錄的裸 binary（fallback）
-                let local_ui = exe_dir.join("claude-confirm-ui");
-                if local_ui.exists() {
-                    return Ok(local_ui.to_string_lossy().to_string());
+            }
+        } else {
+            request.input.clone()
+        };
+
+        if selected(1) {
+            if let Ok(mut rules) = self.session_rules.lock() {
+                if !rules.contains(&rule) {
+                    rules.push(rule.clone());
                 }
             }
         }
-
-        // 嘗試全局命令
-        if Command::new("claude-confirm-ui")
-            .arg("--version")
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
-        {
-            return Ok("claude-confirm-ui".to_string());
+        if selected(2) {
+            if let Some(root) = project_root.as_deref() {
+                if let Err(e) = permissions::save_project_rule(root, rule) {
+                    log::warn!("儲存專案權限規則失敗: {}", e);
+                }
+            }
         }
 
-        anyhow::bail!("找不到 UI 程序。請確保 claude-confirm.app 已安裝到 /Applications/ 或 claude-confirm-ui 在同目錄");
+        Ok(permission_result(allow(updated_input)))
+    }
+}
+
+/// 由 JSON schema 建立工具定義
+fn tool(name: &'static str, description: String, schema: serde_json::Value) -> Result<Tool, McpError> {
+    if let serde_json::Value::Object(schema_map) = schema {
+        Ok(Tool::new(name, description, Arc::new(schema_map)))
+    } else {
+        Err(McpError::internal_error(
+            "無法創建工具 schema".to_string(),
+            None
+        ))
     }
 }
 
+/// 例如「@main.rs → src/main.rs（檔案，1.2 KB，git: M）: /repo/src/main.rs」，
+/// 行範圍引用為「src/main.rs:40-60（…）」
+fn mention_line(mention: &Mention) -> String {
+    let mut details = vec![if mention.is_directory { "資料夾" } else { "檔案" }.to_string()];
+    if let Some(size) = mention.size {
+        details.push(format_size(size));
+    }
+    if let Some(status) = &mention.git_status {
+        details.push(format!("git: {}", status.trim()));
+    }
+
+    let location = match mention.lines {
+        Some(range) => format!("{}:{}-{}", mention.relative, range.start, range.end),
+        None => mention.relative.clone(),
+    };
+    let target = if mention.text.trim_end_matches('/') == location {
+        location
+    } else {
+        format!("@{} → {}", mention.text, location)
+    };
+    format!("{}（{}）: {}", target, details.join("，"), mention.path)
+}
+
+/// 例如「`handle_confirm`（method，McpServer）src/mcp/server.rs:240-310: /repo/src/mcp/server.rs」
+fn symbol_line(symbol: &Symbol) -> String {
+    let details = match &symbol.container {
+        Some(container) => format!("{}，{}", symbol.kind, container),
+        None => symbol.kind.clone(),
+    };
+    format!(
+        "`{}`（{}）{}:{}-{}: {}",
+        symbol.name, details, symbol.relative, symbol.lines.start, symbol.lines.end, symbol.path
+    )
+}
+
+/// 例如「app.log（text/plain，12.0 KB，已截斷）」，專案內的檔案附上路徑
+fn attachment_line(attachment: &Attachment) -> String {
+    let mut details = vec![attachment.mime.clone(), format_size(attachment.size)];
+    match &attachment.content {
+        Some(AttachmentContent::Text(_)) if attachment.truncated => details.push("已截斷".into()),
+        Some(AttachmentContent::Base64(_)) if attachment.mime.starts_with("image/") => {
+            details.push("圖片附於回應中".into())
+        }
+        Some(AttachmentContent::Base64(_)) => details.push("二進位內容未顯示".into()),
+        _ => {}
+    }
+    match (&attachment.relative, &attachment.path) {
+        (Some(relative), Some(path)) => {
+            format!("{} → {}（{}）: {}", attachment.name, relative, details.join("，"), path)
+        }
+        _ => format!("{}（{}）", attachment.name, details.join("，")),
+    }
+}
+
+fn allow(updated_input: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "behavior": "allow",
+        "updatedInput": updated_input,
+    })
+}
+
+/// permission prompt tool 的回傳格式：以 JSON 字串作為文字內容
+fn permission_result(decision: serde_json::Value) -> CallToolResult {
+    CallToolResult::success(vec![Content::text(decision.to_string())])
+}
+
 pub async fn run_server() -> Result<()> {
-    let service = ConfirmServer
+    let service = ConfirmServer::default()
         .serve(stdio())
         .await
         .inspect_err(|e| {
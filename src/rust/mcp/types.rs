@@ -33,3 +33,13 @@ impl From<ConfirmRequest> for crate::types::PopupRequest {
         }
     }
 }
+
+/// MCP approve_tool_use 請求（Claude Code `--permission-prompt-tool`）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApproveToolUseRequest {
+    pub tool_name: String,
+    #[serde(default)]
+    pub input: serde_json::Value,
+    #[serde(default)]
+    pub tool_use_id: Option<String>,
+}
@@ -0,0 +1,318 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::process::Command;
+
+use crate::config::Config;
+use crate::export;
+use crate::history::{self, HistoryEntry};
+use crate::types::{PopupRequest, UserResponse};
+
+/// 等待 UI host 啟動並寫出連線資訊的時間上限
+const HOST_STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+/// 連線到 UI host 的時間上限
+const HOST_CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+/// 送出請求後等待 UI host 確認收到的時間上限
+const HOST_ACK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 彈窗互動錯誤
+#[derive(Debug, thiserror::Error)]
+pub enum PopupError {
+    #[error("等待用戶回應逾時（{0} 秒）")]
+    Timeout(u64),
+}
+
+/// 常駐 UI host 的連線資訊（寫在 [`host_file`]）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostInfo {
+    pub port: u16,
+    pub pid: u32,
+    /// 連線時必須附上的 token，避免其他本機用戶送出請求
+    pub token: String,
+}
+
+/// 送往 UI host 的一行 JSON
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HostRequest {
+    pub token: String,
+    pub request: PopupRequest,
+}
+
+/// UI host 收到請求後立即回覆的一行 JSON
+///
+/// 連線資訊檔可能過期，port 已被其他程序重用；收不到確認時不會無限期等待回應。
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HostAck {
+    /// 收到的請求 ID
+    pub accepted: String,
+}
+
+/// UI host 連線資訊檔的位置
+pub fn host_file() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("claude-confirm").join("ui-host.json"))
+}
+
+/// 顯示請求並等待用戶回應
+///
+/// 設定 `ui.host = true` 時交給常駐的 UI host（沒有時會自動啟動），讓多個請求共用同一個視窗與托盤；
+/// 預設或 host 無法使用時，每個請求啟動一個 UI 程序。
+/// 指定 `timeout` 時，逾時會撤回請求並回傳 [`PopupError::Timeout`]。
+/// 依 `history.enabled` 寫入歷史紀錄。
+pub async fn show_popup(request: &PopupRequest, timeout: Option<Duration>) -> Result<UserResponse> {
+    let config = Config::load();
+    show(request, timeout, &config, config.history.enabled).await
+}
+
+/// 顯示工具調用確認（hook 與 `approve_tool_use`）
+///
+/// 內容包含指令與檔案內容，只有同時開啟 `history.tool_calls` 時才寫入歷史紀錄。
+pub async fn show_tool_use_popup(
+    request: &PopupRequest,
+    timeout: Option<Duration>,
+) -> Result<UserResponse> {
+    let config = Config::load();
+    let record = config.history.enabled && config.history.tool_calls;
+    show(request, timeout, &config, record).await
+}
+
+async fn show(
+    request: &PopupRequest,
+    timeout: Option<Duration>,
+    config: &Config,
+    record: bool,
+) -> Result<UserResponse> {
+    let created_at = chrono::Local::now();
+
+    let response = if config.ui.host {
+        match send_to_host(request, timeout).await {
+            Ok(response) => response,
+            Err(e) if e.downcast_ref::<PopupError>().is_some() => return Err(e),
+            Err(e) => {
+                log::warn!("UI host 無法使用，改為單次視窗: {:#}", e);
+                show_standalone(request, timeout).await?
+            }
+        }
+    } else {
+        show_standalone(request, timeout).await?
+    };
+
+    if !record {
+        return Ok(response);
+    }
+
+    let entry = HistoryEntry {
+        id: request.id.clone(),
+        created_at,
+        answered_at: chrono::Local::now(),
+        request: request.clone(),
+        response: response.clone(),
+    };
+    if let Err(e) = history::record(&entry) {
+        log::warn!("寫入歷史紀錄失敗: {:#}", e);
+    }
+    archive(entry).await;
+
+    Ok(response)
+}
+
+/// 依設定封存互動（`export.archive`）並寫入專案內的互動紀錄（`sessions`）
+///
+/// PDF 排版較慢，在背景執行緒進行。
+async fn archive(entry: HistoryEntry) {
+    let result = tokio::task::spawn_blocking(move || {
+        let archived = export::archive(&entry);
+        let session = export::record_session(&entry);
+        (archived, session)
+    })
+    .await;
+    let (archived, session) = match result {
+        Ok(result) => result,
+        Err(e) => {
+            log::warn!("封存互動失敗: {}", e);
+            return;
+        }
+    };
+
+    match archived {
+        Ok(paths) => {
+            for path in paths {
+                log::info!("已封存: {}", path.display());
+            }
+        }
+        Err(e) => log::warn!("封存互動失敗: {:#}", e),
+    }
+    match session {
+        Ok(Some(path)) => log::info!("已寫入互動紀錄: {}", path.display()),
+        Ok(None) => {}
+        Err(e) => log::warn!("寫入專案互動紀錄失敗: {:#}", e),
+    }
+}
+
+/// 透過 TCP 把請求交給 UI host，並等待這個請求的回應
+async fn send_to_host(request: &PopupRequest, timeout: Option<Duration>) -> Result<UserResponse> {
+    let (host, stream) = connect_host().await?;
+    let (reader, mut writer) = stream.into_split();
+
+    let message = HostRequest {
+        token: host.token,
+        request: request.clone(),
+    };
+    writer
+        .write_all(format!("{}\n", serde_json::to_string(&message)?).as_bytes())
+        .await?;
+
+    let mut lines = BufReader::new(reader).lines();
+    let ack = tokio::time::timeout(HOST_ACK_TIMEOUT, lines.next_line())
+        .await
+        .context("UI host 沒有確認收到請求")??
+        .context("UI host 在確認前關閉了連線")?;
+    let ack: HostAck = serde_json::from_str(&ack).context("連線的對象不是 UI host")?;
+    anyhow::ensure!(ack.accepted == request.id, "UI host 確認的請求 ID 不符");
+
+    // 逾時直接放棄連線，host 會偵測到斷線並撤回請求
+    let line = match timeout {
+        Some(limit) => tokio::time::timeout(limit, lines.next_line())
+            .await
+            .map_err(|_| PopupError::Timeout(limit.as_secs()))??,
+        None => lines.next_line().await?,
+    };
+
+    let line = line.context("UI host 在回應前關閉了連線")?;
+    Ok(serde_json::from_str(&line).unwrap_or_else(|_| UserResponse::cancelled()))
+}
+
+/// 連線到執行中的 UI host，沒有時啟動一個
+///
+/// 多個 MCP 服務器可能同時啟動 host；host 啟動時會先取得獨佔鎖，搶不到鎖的會直接結束。
+async fn connect_host() -> Result<(HostInfo, TcpStream)> {
+    if let Some(connected) = try_connect().await {
+        return Ok(connected);
+    }
+
+    let ui_command = find_ui_command()?;
+    Command::new(&ui_command)
+        .arg("--host")
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .context("啟動 UI host 失敗")?;
+
+    let deadline = tokio::time::Instant::now() + HOST_STARTUP_TIMEOUT;
+    while tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        if let Some(connected) = try_connect().await {
+            return Ok(connected);
+        }
+    }
+
+    anyhow::bail!("UI host 未在 {} 秒內啟動", HOST_STARTUP_TIMEOUT.as_secs());
+}
+
+async fn try_connect() -> Option<(HostInfo, TcpStream)> {
+    let content = tokio::fs::read_to_string(host_file()?).await.ok()?;
+    let host: HostInfo = serde_json::from_str(&content).ok()?;
+    let stream = tokio::time::timeout(
+        HOST_CONNECT_TIMEOUT,
+        TcpStream::connect(("127.0.0.1", host.port)),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    Some((host, stream))
+}
+
+/// 舊模式：每個請求啟動一個 UI 程序，從 stdout 讀取回應
+async fn show_standalone(request: &PopupRequest, timeout: Option<Duration>) -> Result<UserResponse> {
+    // 創建臨時文件
+    let temp_dir = std::env::temp_dir();
+    let temp_file = temp_dir.join(format!("mcp_request_{}.json", request.id));
+
+    let request_json = serde_json::to_string_pretty(request)?;
+    tokio::fs::write(&temp_file, request_json).await?;
+
+    let result = run_ui(&temp_file, timeout).await;
+
+    // 清理臨時文件
+    let _ = tokio::fs::remove_file(&temp_file).await;
+
+    result
+}
+
+async fn run_ui(request_file: &Path, timeout: Option<Duration>) -> Result<UserResponse> {
+    // 查找 UI 命令
+    let ui_command = find_ui_command()?;
+
+    // 調用 UI 程序（逾時放棄等待時，drop 會一併結束子程序）
+    let child = Command::new(&ui_command)
+        .arg("--mcp-request")
+        .arg(request_file)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let output = match timeout {
+        Some(limit) => tokio::time::timeout(limit, child.wait_with_output())
+            .await
+            .map_err(|_| PopupError::Timeout(limit.as_secs()))??,
+        None => child.wait_with_output().await?,
+    };
+
+    if output.status.success() {
+        let response_text = String::from_utf8_lossy(&output.stdout);
+        let response: UserResponse = serde_json::from_str(response_text.trim())
+            .unwrap_or_else(|_| UserResponse::cancelled());
+        Ok(response)
+    } else {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("UI 程序失敗: {}", error);
+    }
+}
+
+pub(crate) fn find_ui_command() -> Result<String> {
+    // macOS: 優先使用 .app bundle 內的 binary（需要 Info.plist 才能啟動 WebView）
+    let app_bundle_paths = [
+        "/Applications/claude-confirm.app/Contents/MacOS/claude-confirm-ui",
+        // homebrew cask 或自訂安裝位置
+        &format!("{}/Applications/claude-confirm.app/Contents/MacOS/claude-confirm-ui",
+            dirs::home_dir().map(|h| h.to_string_lossy().to_string()).unwrap_or_default()),
+    ];
+    for path in &app_bundle_paths {
+        let p = std::path::Path::new(path);
+        if p.exists() {
+            return Ok(path.to_string());
+        }
+    }
+
+    // 查找同目錄的 .app bundle
+    if let Ok(current_exe) = std::env::current_exe() {
+        if let Some(exe_dir) = current_exe.parent() {
+            // 同目錄的 .app bundle
+            let bundle_ui = exe_dir.join("bundle/macos/claude-confirm.app/Contents/MacOS/claude-confirm-ui");
+            if bundle_ui.exists() {
+                return Ok(bundle_ui.to_string_lossy().to_string());
+            }
+            // 同目錄的裸 binary（fallback）
+            let local_ui = exe_dir.join("claude-confirm-ui");
+            if local_ui.exists() {
+                return Ok(local_ui.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    // 嘗試全局命令
+    if std::process::Command::new("claude-confirm-ui")
+        .arg("--version")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+    {
+        return Ok("claude-confirm-ui".to_string());
+    }
+
+    anyhow::bail!("找不到 UI 程序。請確保 claude-confirm.app 已安裝到 /Applications/ 或 claude-confirm-ui 在同目錄");
+}
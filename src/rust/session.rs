@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// 往上追溯父進程的最大層數
+const MAX_PROCESS_DEPTH: usize = 10;
+
+/// 終端 session 識別資訊，用於判斷是哪個終端發出的請求
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionInfo {
+    #[serde(default)]
+    pub hostname: Option<String>,
+    #[serde(default)]
+    pub user: Option<String>,
+    /// SSH 連線來源（`SSH_CONNECTION` 的 client ip:port）
+    #[serde(default)]
+    pub ssh: Option<String>,
+    #[serde(default)]
+    pub tmux: Option<TmuxInfo>,
+    /// GNU screen session（`STY`）與視窗編號
+    #[serde(default)]
+    pub screen: Option<String>,
+    /// 容器環境：docker、podman、devcontainer、codespaces 等
+    #[serde(default)]
+    pub container: Option<String>,
+    /// 父進程鏈，由近到遠
+    #[serde(default)]
+    pub process_chain: Vec<ProcessInfo>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TmuxInfo {
+    /// tmux session 名稱
+    #[serde(default)]
+    pub session: Option<String>,
+    /// `session:window.pane` 目標，可直接用於 `tmux select-pane -t`
+    #[serde(default)]
+    pub target: Option<String>,
+    /// pane ID（`TMUX_PANE`，例如 `%3`）
+    pub pane_id: String,
+    /// tmux server socket（`TMUX` 的第一段）
+    #[serde(default)]
+    pub socket: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+}
+
+impl SessionInfo {
+    /// 從目前進程的環境偵測
+    pub fn detect() -> Self {
+        Self {
+            hostname: hostname(),
+            user: std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .ok(),
+            ssh: std::env::var("SSH_CONNECTION")
+                .ok()
+                .and_then(|conn| {
+                    let mut parts = conn.split_whitespace();
+                    Some(format!("{}:{}", parts.next()?, parts.next()?))
+                })
+                .or_else(|| std::env::var("SSH_TTY").ok()),
+            tmux: TmuxInfo::detect(),
+            screen: std::env::var("STY").ok().map(|sty| match std::env::var("WINDOW") {
+                Ok(window) => format!("{} #{}", sty, window),
+                Err(_) => sty,
+            }),
+            container: detect_container(),
+            process_chain: process_chain(std::process::id()),
+        }
+    }
+}
+
+impl TmuxInfo {
+    fn detect() -> Option<Self> {
+        let tmux = std::env::var("TMUX").ok()?;
+        let pane_id = std::env::var("TMUX_PANE").ok()?;
+        let socket = tmux.split(',').next().filter(|s| !s.is_empty()).map(String::from);
+
+        // session 名稱與 window/pane 編號需要詢問 tmux server
+        let mut command = Command::new("tmux");
+        if let Some(socket) = &socket {
+            command.arg("-S").arg(socket);
+        }
+        let described = command
+            .args(["display-message", "-p", "-t", &pane_id, "#S\t#S:#I.#P"])
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string());
+
+        let (session, target) = match described.as_deref().and_then(|d| d.split_once('\t')) {
+            Some((session, target)) => (Some(session.to_string()), Some(target.to_string())),
+            None => (None, None),
+        };
+
+        Some(Self {
+            session,
+            target,
+            pane_id,
+            socket,
+        })
+    }
+}
+
+fn hostname() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    if let Ok(name) = std::fs::read_to_string("/proc/sys/kernel/hostname") {
+        return Some(name.trim().to_string());
+    }
+
+    if let Ok(name) = std::env::var("HOSTNAME").or_else(|_| std::env::var("COMPUTERNAME")) {
+        return Some(name);
+    }
+
+    Command::new("hostname")
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+fn detect_container() -> Option<String> {
+    if std::env::var("CODESPACES").is_ok_and(|v| v == "true") {
+        return Some("codespaces".to_string());
+    }
+    if std::env::var("REMOTE_CONTAINERS").is_ok_and(|v| v == "true") {
+        return Some("devcontainer".to_string());
+    }
+    // podman / systemd-nspawn 等會設定 `container` 環境變數
+    if let Ok(runtime) = std::env::var("container") {
+        return Some(runtime);
+    }
+    if std::path::Path::new("/run/.containerenv").exists() {
+        return Some("podman".to_string());
+    }
+    if std::path::Path::new("/.dockerenv").exists() {
+        return Some("docker".to_string());
+    }
+
+    let cgroup = std::fs::read_to_string("/proc/1/cgroup").unwrap_or_default();
+    ["docker", "kubepods", "containerd", "lxc"]
+        .into_iter()
+        .find(|runtime| cgroup.contains(runtime))
+        .map(String::from)
+}
+
+/// 從 `pid` 的父進程開始往上追溯，直到 init 或達到上限
+fn process_chain(pid: u32) -> Vec<ProcessInfo> {
+    let mut chain = Vec::new();
+    let mut current = parent_of(pid);
+
+    while let Some(ppid) = current {
+        if ppid <= 1 || chain.len() >= MAX_PROCESS_DEPTH {
+            break;
+        }
+        let Some(name) = process_name(ppid) else {
+            break;
+        };
+        chain.push(ProcessInfo { pid: ppid, name });
+        current = parent_of(ppid);
+    }
+
+    chain
+}
+
+#[cfg(target_os = "linux")]
+fn parent_of(pid: u32) -> Option<u32> {
+    // /proc/<pid>/stat: pid (comm) state ppid ...，comm 可能含空白與括號
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let after_comm = &stat[stat.rfind(')')? + 1..];
+    after_comm.split_whitespace().nth(1)?.parse().ok()
+}
+
+#[cfg(target_os = "linux")]
+fn process_name(pid: u32) -> Option<String> {
+    std::fs::read_to_string(format!("/proc/{}/comm", pid))
+        .ok()
+        .map(|name| name.trim().to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn parent_of(pid: u32) -> Option<u32> {
+    ps_field(pid, "ppid=")?.parse().ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_name(pid: u32) -> Option<String> {
+    let command = ps_field(pid, "comm=")?;
+    // macOS 的 comm 是完整路徑，只保留檔名
+    Some(command.rsplit('/').next().unwrap_or(&command).to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn ps_field(pid: u32, field: &str) -> Option<String> {
+    Command::new("ps")
+        .args(["-o", field, "-p", &pid.to_string()])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|value| !value.is_empty())
+}
@@ -0,0 +1,141 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+/// 內容預覽的最大行數，避免大型 Write 撐爆彈窗
+const MAX_PREVIEW_LINES: usize = 200;
+
+/// 工具調用的主要輸入：Bash 指令、檔案路徑或 URL
+pub fn primary_input(tool_name: &str, input: &Value) -> Option<String> {
+    let key = match tool_name {
+        "Bash" => "command",
+        "Read" | "Write" | "Edit" | "MultiEdit" => "file_path",
+        "NotebookEdit" => "notebook_path",
+        "WebFetch" => "url",
+        "WebSearch" => "query",
+        "Glob" | "Grep" => "pattern",
+        _ => return None,
+    };
+    input.get(key).and_then(Value::as_str).map(String::from)
+}
+
+/// 將工具調用渲染成易讀的 Markdown
+pub fn render_markdown(tool_name: &str, input: &Value) -> String {
+    let mut md = format!("## 🛠️ 工具調用確認：`{}`\n\n", tool_name);
+    let str_field = |key: &str| input.get(key).and_then(Value::as_str);
+
+    match tool_name {
+        "Bash" => {
+            md.push_str("**指令**\n\n");
+            md.push_str(&fenced("bash", str_field("command").unwrap_or_default()));
+            if let Some(description) = str_field("description") {
+                md.push_str(&format!("\n**說明**：{}\n", description));
+            }
+        }
+        "Write" => {
+            md.push_str(&file_line(str_field("file_path")));
+            let content = str_field("content").unwrap_or_default();
+            md.push_str(&format!("\n**內容**（{} 行）\n\n", content.lines().count()));
+            md.push_str(&fenced(&language_of(str_field("file_path")), &truncate_lines(content)));
+        }
+        "Edit" => {
+            md.push_str(&file_line(str_field("file_path")));
+            if input.get("replace_all").and_then(Value::as_bool) == Some(true) {
+                md.push_str("\n> 取代所有出現位置\n");
+            }
+            md.push('\n');
+            md.push_str(&edit_diff(
+                str_field("old_string").unwrap_or_default(),
+                str_field("new_string").unwrap_or_default(),
+            ));
+        }
+        "MultiEdit" => {
+            md.push_str(&file_line(str_field("file_path")));
+            let edits = input.get("edits").and_then(Value::as_array).cloned().unwrap_or_default();
+            for (i, edit) in edits.iter().enumerate() {
+                md.push_str(&format!("\n**修改 {}**\n\n", i + 1));
+                md.push_str(&edit_diff(
+                    edit.get("old_string").and_then(Value::as_str).unwrap_or_default(),
+                    edit.get("new_string").and_then(Value::as_str).unwrap_or_default(),
+                ));
+            }
+        }
+        _ => {
+            if let Some(primary) = primary_input(tool_name, input) {
+                md.push_str(&format!("**目標**：`{}`\n\n", primary));
+            }
+            let json = serde_json::to_string_pretty(input).unwrap_or_default();
+            md.push_str(&fenced("json", &truncate_lines(&json)));
+        }
+    }
+
+    md
+}
+
+/// 依用戶輸入產生修改後的工具參數
+///
+/// Bash 直接以輸入取代 `command`，其他工具需輸入完整的 JSON 物件。
+pub fn edited_input(tool_name: &str, input: &Value, edited: &str) -> Result<Value> {
+    if tool_name == "Bash" {
+        let mut updated = input.clone();
+        let object = updated.as_object_mut().context("Bash 參數不是 JSON 物件")?;
+        object.insert("command".to_string(), Value::String(edited.to_string()));
+        return Ok(updated);
+    }
+
+    let updated: Value = serde_json::from_str(edited).context("不是有效的 JSON")?;
+    anyhow::ensure!(updated.is_object(), "參數必須是 JSON 物件");
+    Ok(updated)
+}
+
+/// 修改參數時的輸入提示
+pub fn edit_hint(tool_name: &str) -> &'static str {
+    if tool_name == "Bash" {
+        "在下方輸入框輸入新的指令，確認後以此指令執行"
+    } else {
+        "在下方輸入框輸入新的 JSON 參數，確認後以此參數執行"
+    }
+}
+
+fn file_line(path: Option<&str>) -> String {
+    format!("**檔案**：`{}`\n", path.unwrap_or("(未指定)"))
+}
+
+/// 舊內容全部標為刪除、新內容全部標為新增
+fn edit_diff(old: &str, new: &str) -> String {
+    let mut diff = String::new();
+    for line in old.lines() {
+        diff.push_str(&format!("-{}\n", line));
+    }
+    for line in new.lines() {
+        diff.push_str(&format!("+{}\n", line));
+    }
+    fenced("diff", &truncate_lines(&diff))
+}
+
+/// 用足夠長的 fence 包住內容，避免內容本身含有 ```
+pub(crate) fn fenced(language: &str, content: &str) -> String {
+    let mut fence = "```".to_string();
+    while content.contains(&fence) {
+        fence.push('`');
+    }
+    format!("{}{}\n{}\n{}\n", fence, language, content.trim_end_matches('\n'), fence)
+}
+
+fn truncate_lines(content: &str) -> String {
+    let total = content.lines().count();
+    if total <= MAX_PREVIEW_LINES {
+        return content.to_string();
+    }
+    let mut head: Vec<&str> = content.lines().take(MAX_PREVIEW_LINES).collect();
+    let note = format!("… (省略其餘 {} 行)", total - MAX_PREVIEW_LINES);
+    head.push(&note);
+    head.join("\n")
+}
+
+/// 依副檔名推測程式碼區塊語言
+pub(crate) fn language_of(path: Option<&str>) -> String {
+    path.and_then(|p| std::path::Path::new(p).extension())
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_string()
+}
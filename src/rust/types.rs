@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use crate::git::GitInfo;
+use crate::session::SessionInfo;
+
 /// Popup 請求結構
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PopupRequest {
@@ -33,11 +36,43 @@ pub struct EnvContext {
     /// 進程 ID
     #[serde(default)]
     pub pid: Option<u32>,
+    /// Git 儲存庫狀態（cwd 不在儲存庫內時為 None）
+    #[serde(default)]
+    pub git: Option<GitInfo>,
+    /// 終端 session 識別（主機、SSH、tmux/screen、容器、父進程鏈）
+    #[serde(default)]
+    pub session: Option<SessionInfo>,
+    /// 發出請求的 MCP 客戶端（initialize 時取得）
+    #[serde(default)]
+    pub client: Option<McpClientInfo>,
+}
+
+/// MCP 客戶端資訊
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct McpClientInfo {
+    /// 客戶端名稱，例如 `claude-code`、`claude-ai`
+    pub name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// 顯示用名稱
+    #[serde(default)]
+    pub title: Option<String>,
 }
 
 impl EnvContext {
     /// 從當前環境自動偵測
     pub fn detect() -> Self {
+        Self::detect_with(GitInfo::detect)
+    }
+
+    /// 不執行 `git status` 的偵測，git 只讀取 `.git` 內的分支與 HEAD
+    ///
+    /// 供 hook 模式使用：每次高風險工具調用都會執行，不能被大型儲存庫拖慢。
+    pub fn detect_quick() -> Self {
+        Self::detect_with(GitInfo::detect_head)
+    }
+
+    fn detect_with(detect_git: fn(&std::path::Path) -> Option<GitInfo>) -> Self {
         let cwd = std::env::current_dir()
             .ok()
             .and_then(|p| p.to_str().map(String::from));
@@ -51,16 +86,24 @@ impl EnvContext {
         
         let terminal = std::env::var("TERM_PROGRAM").ok();
         let pid = Some(std::process::id());
+        let git = cwd
+            .as_deref()
+            .and_then(|path| detect_git(std::path::Path::new(path)));
         
         Self {
             cwd,
             project_name,
             terminal,
             pid,
+            git,
+            session: Some(SessionInfo::detect()),
+            client: None,
         }
     }
     
     /// 合併兩個 EnvContext，優先使用 other 的值（如果有）
+    ///
+    /// other 覆蓋了 cwd 但沒有提供 git 資訊時，會針對新的 cwd 重新偵測。
     pub fn merge_with(&self, other: Option<&EnvContext>) -> Self {
         match other {
             Some(o) => Self {
@@ -68,6 +111,15 @@ impl EnvContext {
                 project_name: o.project_name.clone().or_else(|| self.project_name.clone()),
                 terminal: o.terminal.clone().or_else(|| self.terminal.clone()),
                 pid: o.pid.or(self.pid),
+                git: match (&o.git, &o.cwd) {
+                    (Some(git), _) => Some(git.clone()),
+                    (None, Some(cwd)) if o.cwd != self.cwd => {
+                        GitInfo::detect(std::path::Path::new(cwd))
+                    }
+                    _ => self.git.clone(),
+                },
+                session: o.session.clone().or_else(|| self.session.clone()),
+                client: o.client.clone().or_else(|| self.client.clone()),
             },
             None => self.clone(),
         }
@@ -93,6 +145,15 @@ pub struct UserResponse {
     pub user_input: String,
     #[serde(default)]
     pub images: Vec<String>,
+    /// `user_input` 中以 `@` 引用的檔案與資料夾
+    #[serde(default)]
+    pub mentions: Vec<Mention>,
+    /// `user_input` 中以 `#` 引用的函式、型別等符號
+    #[serde(default)]
+    pub symbols: Vec<Symbol>,
+    /// 拖放、選擇或貼上的附件
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
 }
 
 impl UserResponse {
@@ -102,6 +163,9 @@ impl UserResponse {
             selected_sections: Vec::new(),
             user_input: String::new(),
             images: Vec::new(),
+            mentions: Vec::new(),
+            symbols: Vec::new(),
+            attachments: Vec::new(),
         }
     }
 
@@ -111,6 +175,91 @@ impl UserResponse {
             selected_sections,
             user_input,
             images,
+            mentions: Vec::new(),
+            symbols: Vec::new(),
+            attachments: Vec::new(),
         }
     }
 }
+
+/// 用戶以 `@` 引用的檔案或資料夾
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mention {
+    /// 輸入中的原始文字（不含 `@`）
+    pub text: String,
+    /// 絕對路徑
+    pub path: String,
+    /// 相對於專案根目錄的路徑
+    pub relative: String,
+    pub is_directory: bool,
+    /// 檔案大小（bytes），資料夾為 None
+    #[serde(default)]
+    pub size: Option<u64>,
+    /// git 狀態（porcelain 的 XY 代碼，例如 ` M`、`A `、`??`），未變更時為 None
+    #[serde(default)]
+    pub git_status: Option<String>,
+    /// `@path:40-60` 形式引用的行範圍
+    #[serde(default)]
+    pub lines: Option<LineRange>,
+    /// 行範圍內的文字
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+/// 程式碼中的符號（函式、型別、類別等）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symbol {
+    pub name: String,
+    /// 例如 `function`、`struct`、`class`、`method`
+    pub kind: String,
+    /// 所屬的 impl、trait、class 或模組
+    #[serde(default)]
+    pub container: Option<String>,
+    /// 檔案絕對路徑
+    pub path: String,
+    /// 相對於專案根目錄的路徑
+    pub relative: String,
+    /// 定義所在的行範圍
+    pub lines: LineRange,
+}
+
+/// 行範圍（從 1 開始，包含頭尾）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// 用戶附加的檔案
+///
+/// 專案內的檔案只回傳路徑；專案外的檔案與貼上的內容會附上（有大小上限的）內容。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: String,
+    /// 檔名
+    pub name: String,
+    /// 依內容與副檔名判斷的 MIME 類型
+    pub mime: String,
+    /// 原始大小（bytes）
+    pub size: u64,
+    /// 專案內檔案的絕對路徑
+    #[serde(default)]
+    pub path: Option<String>,
+    /// 相對於專案根目錄的路徑
+    #[serde(default)]
+    pub relative: Option<String>,
+    /// 專案外檔案或貼上的內容
+    #[serde(default)]
+    pub content: Option<AttachmentContent>,
+    /// 文字內容超過單檔上限，只包含開頭部分
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// 附件內容：文字直接保存，其他以 base64 編碼
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "encoding", content = "data", rename_all = "snake_case")]
+pub enum AttachmentContent {
+    Text(String),
+    Base64(String),
+}
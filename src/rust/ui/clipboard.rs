@@ -0,0 +1,189 @@
+use anyhow::{bail, Context, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+
+/// X11 上持有剪貼簿內容的子程序參數
+pub const SERVE_ARG: &str = "--serve-clipboard";
+
+/// 系統剪貼簿（不經過 WebView）
+///
+/// X11 上剪貼簿內容由持有者提供，單次模式送出後程序就會結束，
+/// 因此寫入時交給獨立的子程序（[`SERVE_ARG`]）持有，直到其他程式取得擁有權；
+/// 讀取時保留同一個 arboard 實例直到程序結束。
+/// Wayland 上 arboard 需要 compositor 支援 data-control 協定（wlroots、KDE），
+/// 讀寫失敗時（例如 GNOME）改用 `wl-paste` / `wl-copy`。
+#[derive(Clone, Default)]
+pub struct Clipboard {
+    inner: Arc<Mutex<Option<arboard::Clipboard>>>,
+}
+
+impl Clipboard {
+    /// 讀取文字，剪貼簿中沒有文字時回傳 None
+    pub fn read_text(&self) -> Result<Option<String>> {
+        let text = match self.with(|clipboard| clipboard.get_text()) {
+            Ok(text) => Some(text),
+            Err(arboard::Error::ContentNotAvailable) if !is_wayland() => None,
+            Err(e) if !is_wayland() => return Err(e).context("讀取剪貼簿文字失敗"),
+            Err(e) => {
+                log::debug!("arboard 讀取文字失敗，改用 wl-paste: {}", e);
+                wl_paste("text/plain;charset=utf-8")?
+                    .and_then(|bytes| String::from_utf8(bytes).ok())
+            }
+        };
+        Ok(text.filter(|text| !text.is_empty()))
+    }
+
+    /// 讀取圖片並編碼為 PNG，剪貼簿中沒有圖片時回傳 None
+    pub fn read_image_png(&self) -> Result<Option<Vec<u8>>> {
+        match self.with(|clipboard| clipboard.get_image()) {
+            Ok(image) => encode_png(image.width, image.height, &image.bytes).map(Some),
+            Err(arboard::Error::ContentNotAvailable) if !is_wayland() => Ok(None),
+            Err(e) if !is_wayland() => Err(e).context("讀取剪貼簿圖片失敗"),
+            Err(e) => {
+                log::debug!("arboard 讀取圖片失敗，改用 wl-paste: {}", e);
+                wl_paste("image/png")
+            }
+        }
+    }
+
+    pub fn write_text(&self, text: &str) -> Result<()> {
+        if is_x11() {
+            match spawn_server(text) {
+                Ok(()) => return Ok(()),
+                Err(e) => log::debug!("無法啟動剪貼簿子程序，改由本程序持有: {:#}", e),
+            }
+        }
+        match self.with(|clipboard| clipboard.set_text(text)) {
+            Ok(()) => Ok(()),
+            Err(e) if is_wayland() => {
+                log::debug!("arboard 寫入失敗，改用 wl-copy: {}", e);
+                wl_copy(text)
+            }
+            Err(e) => Err(e).context("寫入剪貼簿失敗"),
+        }
+    }
+
+    fn with<T>(
+        &self,
+        f: impl FnOnce(&mut arboard::Clipboard) -> Result<T, arboard::Error>,
+    ) -> Result<T, arboard::Error> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.is_none() {
+            *inner = Some(arboard::Clipboard::new()?);
+        }
+        match inner.as_mut() {
+            Some(clipboard) => f(clipboard),
+            None => Err(arboard::Error::ClipboardNotSupported),
+        }
+    }
+}
+
+fn is_wayland() -> bool {
+    cfg!(target_os = "linux") && std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+fn is_x11() -> bool {
+    cfg!(target_os = "linux") && !is_wayland() && std::env::var_os("DISPLAY").is_some()
+}
+
+/// 啟動持有剪貼簿的子程序，不等待它結束（不繼承 stdout，MCP 服務器不會等它）
+fn spawn_server(text: &str) -> Result<()> {
+    let mut child = Command::new(std::env::current_exe()?)
+        .arg(SERVE_ARG)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("無法啟動剪貼簿子程序")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    // 回收子程序，避免常駐 host 留下 zombie
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+/// [`SERVE_ARG`] 子程序：從 stdin 讀取文字並持有剪貼簿，直到其他程式取得擁有權
+#[cfg(target_os = "linux")]
+pub fn serve() -> Result<()> {
+    use arboard::SetExtLinux;
+    use std::io::Read;
+
+    let mut text = String::new();
+    std::io::stdin().read_to_string(&mut text)?;
+    arboard::Clipboard::new()?
+        .set()
+        .wait()
+        .text(text)
+        .context("寫入剪貼簿失敗")
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn serve() -> Result<()> {
+    bail!("{} 只用於 X11", SERVE_ARG)
+}
+
+/// 以 `wl-paste` 讀取指定類型，剪貼簿中沒有此類型時回傳 None
+fn wl_paste(mime: &str) -> Result<Option<Vec<u8>>> {
+    let types = Command::new("wl-paste")
+        .arg("--list-types")
+        .stderr(Stdio::null())
+        .output()
+        .context("無法執行 wl-paste（請安裝 wl-clipboard）")?;
+    let available = String::from_utf8_lossy(&types.stdout);
+    let base = mime.split(';').next().unwrap_or(mime);
+    let Some(mime) = available
+        .lines()
+        .find(|t| *t == mime)
+        .or_else(|| available.lines().find(|t| t.split(';').next() == Some(base)))
+    else {
+        return Ok(None);
+    };
+
+    let output = Command::new("wl-paste")
+        .args(["--no-newline", "--type", mime])
+        .stderr(Stdio::null())
+        .output()
+        .context("無法執行 wl-paste")?;
+    if !output.status.success() {
+        bail!("wl-paste 失敗: {}", output.status);
+    }
+    Ok(Some(output.stdout).filter(|bytes| !bytes.is_empty()))
+}
+
+fn wl_copy(text: &str) -> Result<()> {
+    let mut child = Command::new("wl-copy")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("無法執行 wl-copy（請安裝 wl-clipboard）")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    // wl-copy 讀完 stdin 後會在背景繼續提供內容
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("wl-copy 失敗: {}", status);
+    }
+    Ok(())
+}
+
+/// 把 arboard 的 RGBA 像素編碼為 PNG
+fn encode_png(width: usize, height: usize, rgba: &[u8]) -> Result<Vec<u8>> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| {
+            writer.write_image_data(rgba)?;
+            writer.finish()
+        })
+        .context("編碼 PNG 失敗")?;
+    Ok(png)
+}
@@ -1,6 +1,15 @@
-use tauri::{command, AppHandle, Manager};
+use tauri::{command, AppHandle, Manager, State};
+use crate::config::Config;
+use crate::export;
 use crate::types::UserResponse;
-use std::path::Path;
+use crate::update::{self, UpdateChannel};
+use super::clipboard::Clipboard;
+use super::host::{self, QueueSnapshot, RequestQueue};
+use crate::files::{
+    self, persist_attachments, resolve_mentions, AttachmentList, AttachmentStore, FileEntry,
+    FileIndex, FileMatch, FilePreview, SymbolIndex, SymbolMatch,
+};
+use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 use base64::{Engine as _, engine::general_purpose};
 
@@ -9,41 +18,186 @@ pub struct VersionInfo {
     pub current: String,
     pub latest: Option<String>,
     pub has_update: bool,
+    /// 最新版本是預覽版
+    pub prerelease: bool,
+    /// 最新版本的 release 頁面
+    pub url: Option<String>,
+    pub channel: UpdateChannel,
+    /// 可用 `rollback_update` 還原的上一版
+    pub rollback_version: Option<String>,
     pub error: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FileEntry {
-    pub path: String,
-    pub name: String,
-    pub is_directory: bool,
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectFilesResult {
+    pub root: String,
+    pub files: Vec<FileEntry>,
 }
 
+/// 分頁的專案檔案列表
 #[derive(Debug, Serialize, Deserialize)]
-pub struct ProjectFilesResult {
+pub struct ProjectFilesPage {
     pub root: String,
     pub files: Vec<FileEntry>,
+    /// 全部項目數
+    pub total: usize,
+    /// 下一頁的 offset，沒有下一頁時為 None
+    pub next_offset: Option<usize>,
+    /// 達到掃描上限，列表不完整
+    pub truncated: bool,
 }
 
-#[derive(Debug, Deserialize)]
-struct GitHubRelease {
-    tag_name: String,
+/// 模糊搜尋結果，依分數排序
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectFilesSearch {
+    pub root: String,
+    pub files: Vec<FileMatch>,
+}
+
+/// 符號搜尋結果，依分數排序
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SymbolSearch {
+    pub root: String,
+    pub symbols: Vec<SymbolMatch>,
+    /// 背景仍在建立索引，前端稍後應重新搜尋
+    pub indexing: bool,
 }
 
 #[command]
-pub async fn submit_response(response: UserResponse) -> Result<(), String> {
-    // 將結果輸出到 stdout
-    println!("{}", serde_json::to_string(&response).map_err(|e| e.to_string())?);
+pub async fn submit_response(
+    app_handle: AppHandle,
+    queue: State<'_, RequestQueue>,
+    index: State<'_, FileIndex>,
+    symbol_index: State<'_, SymbolIndex>,
+    attachments: State<'_, AttachmentStore>,
+    request_id: Option<String>,
+    mut response: UserResponse,
+) -> Result<(), String> {
+    let id = request_id
+        .or_else(|| queue.active_id())
+        .ok_or("沒有等待回應的請求")?;
+
+    // 附件一律取自後端暫存（已檢查過預算），不採用前端送來的內容；
+    // 舊版的 `images` 不經過預算檢查，一律捨棄
+    response.attachments = attachments.take(&id);
+    response.images.clear();
+    if response.confirmed {
+        response = resolve_response(&queue, &index, &symbol_index, &id, response, true).await?;
+    }
 
-    // 退出程序
-    std::process::exit(0);
+    finish(&app_handle, &queue, &id, response)
+}
+
+/// 由後端解析 @ 引用並確認 # 符號，路徑一律限制在請求的專案目錄內
+///
+/// `persist` 為 true（送出時）才依設定把附件保存到專案目錄。
+async fn resolve_response(
+    queue: &RequestQueue,
+    index: &FileIndex,
+    symbol_index: &SymbolIndex,
+    request_id: &str,
+    mut response: UserResponse,
+    persist: bool,
+) -> Result<UserResponse, String> {
+    let Ok(root) = project_root(queue, Some(request_id.to_string())) else {
+        response.symbols.clear();
+        return Ok(response);
+    };
+
+    let index = index.clone();
+    let symbol_index = symbol_index.clone();
+    let request_id = request_id.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let config = Config::load();
+        let input = &response.user_input;
+        response.mentions = if input.contains('@') {
+            let listing = index.get(&root, &config.files, false).ok();
+            resolve_mentions(&root, input, listing.as_deref())
+        } else {
+            Vec::new()
+        };
+        let chosen = std::mem::take(&mut response.symbols);
+        response.symbols = symbol_index.verify(&root, input, chosen);
+
+        if persist && config.attachments.persist {
+            let saved = persist_attachments(
+                &root,
+                &request_id,
+                &mut response.attachments,
+            );
+            match saved {
+                Ok(Some(dir)) => log::info!("附件已保存到 {}", dir.display()),
+                Ok(None) => {}
+                Err(e) => log::warn!("保存附件失敗，改為附上內容: {}", e),
+            }
+        }
+        response
+    })
+    .await
+    .map_err(|e| format!("處理回應失敗: {}", e))
 }
 
 #[command]
-pub async fn cancel_response() -> Result<(), String> {
-    let response = UserResponse::cancelled();
-    println!("{}", serde_json::to_string(&response).map_err(|e| e.to_string())?);
-    std::process::exit(0);
+pub async fn cancel_response(
+    app_handle: AppHandle,
+    queue: State<'_, RequestQueue>,
+    request_id: Option<String>,
+) -> Result<(), String> {
+    let id = request_id
+        .or_else(|| queue.active_id())
+        .ok_or("沒有等待回應的請求")?;
+    finish(&app_handle, &queue, &id, UserResponse::cancelled())
+}
+
+/// 送出回應、返回原終端機；單次模式下接著退出程序
+///
+/// 取消時丟棄暫存的附件。
+pub(crate) fn finish(
+    app_handle: &AppHandle,
+    queue: &RequestQueue,
+    request_id: &str,
+    mut response: UserResponse,
+) -> Result<(), String> {
+    app_handle.state::<AttachmentStore>().take(request_id);
+    if !response.confirmed {
+        response.attachments.clear();
+    }
+
+    let completed = queue
+        .complete(request_id, response)
+        .ok_or_else(|| format!("找不到請求: {}", request_id))?;
+
+    if let Some(env) = &completed.request.env_context {
+        super::refocus::refocus_terminal(&Config::load().refocus, env);
+    }
+
+    if completed.exit {
+        // 退出程序
+        std::process::exit(0);
+    }
+
+    host::on_queue_changed(app_handle, false);
+    Ok(())
+}
+
+/// 目前所有待處理的請求
+#[command]
+pub fn get_pending_requests(queue: State<'_, RequestQueue>) -> QueueSnapshot {
+    queue.snapshot()
+}
+
+/// 切換目前顯示的請求
+#[command]
+pub fn set_active_request(
+    app_handle: AppHandle,
+    queue: State<'_, RequestQueue>,
+    request_id: String,
+) -> Result<(), String> {
+    if !queue.set_active(&request_id) {
+        return Err(format!("找不到請求: {}", request_id));
+    }
+    host::on_queue_changed(&app_handle, false);
+    Ok(())
 }
 
 #[command]
@@ -82,100 +236,308 @@ pub fn read_mcp_request(file_path: String) -> Result<serde_json::Value, String>
     }
 }
 
+/// 每頁預設的項目數
+const DEFAULT_PAGE_SIZE: usize = 500;
+/// 搜尋預設回傳的結果數
+const SEARCH_LIMIT: usize = 50;
+
+/// 請求的專案根目錄：發出請求時的 `env_context.cwd`
+///
+/// 未指定 `request_id` 時使用目前顯示中的請求。
+fn project_root(queue: &RequestQueue, request_id: Option<String>) -> Result<PathBuf, String> {
+    let id = request_id
+        .or_else(|| queue.active_id())
+        .ok_or("沒有等待回應的請求")?;
+    let request = queue
+        .request(&id)
+        .ok_or_else(|| format!("找不到請求: {}", id))?;
+    let cwd = request
+        .env_context
+        .and_then(|env| env.cwd)
+        .ok_or("請求未提供工作目錄")?;
+    std::fs::canonicalize(&cwd).map_err(|e| format!("無法存取工作目錄 {}: {}", cwd, e))
+}
+
 #[command]
-pub async fn get_project_files() -> Result<Vec<FileEntry>, String> {
-    // 獲取當前工作目錄
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-
-    // 掃描文件和資料夾
-    let mut entries = scan_directory(&current_dir, 3).map_err(|e| e.to_string())?;
-
-    // 排序：資料夾在前，然後按名稱排序
-    entries.sort_by(|a, b| {
-        match (a.is_directory, b.is_directory) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-        }
-    });
+pub async fn get_project_files(
+    queue: State<'_, RequestQueue>,
+    index: State<'_, FileIndex>,
+    request_id: Option<String>,
+) -> Result<Vec<FileEntry>, String> {
+    Ok(get_project_files_with_root(queue, index, request_id).await?.files)
+}
 
-    Ok(entries)
+#[command]
+pub async fn get_project_files_with_root(
+    queue: State<'_, RequestQueue>,
+    index: State<'_, FileIndex>,
+    request_id: Option<String>,
+) -> Result<ProjectFilesResult, String> {
+    let page = list_project_files(queue, index, request_id, 0, Some(usize::MAX), false).await?;
+    Ok(ProjectFilesResult {
+        root: page.root,
+        files: page.files,
+    })
 }
 
+/// 分頁列出專案檔案（遵守 .gitignore）
+///
+/// 讀取快取的索引，檔案變動後自動重新掃描；`refresh` 為 true 時強制重新掃描。
 #[command]
-pub async fn get_project_files_with_root() -> Result<ProjectFilesResult, String> {
-    // 獲取當前工作目錄
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-    let root = current_dir.to_string_lossy().to_string();
-
-    // 掃描文件和資料夾
-    let mut entries = scan_directory(&current_dir, 3).map_err(|e| e.to_string())?;
-
-    // 排序：資料夾在前，然後按名稱排序
-    entries.sort_by(|a, b| {
-        match (a.is_directory, b.is_directory) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-        }
-    });
+pub async fn list_project_files(
+    queue: State<'_, RequestQueue>,
+    index: State<'_, FileIndex>,
+    request_id: Option<String>,
+    offset: usize,
+    limit: Option<usize>,
+    refresh: bool,
+) -> Result<ProjectFilesPage, String> {
+    let root = project_root(&queue, request_id)?;
+    let config = Config::load().files;
+
+    // 大型專案掃描需要時間，避免卡住其他命令
+    let index = index.inner().clone();
+    let listing = tauri::async_runtime::spawn_blocking(move || index.get(&root, &config, refresh))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("掃描專案檔案失敗: {}", e))?;
+
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let files: Vec<FileEntry> = listing.entries.iter().skip(offset).take(limit).cloned().collect();
+    let end = offset.saturating_add(files.len());
+
+    Ok(ProjectFilesPage {
+        root: listing.root.to_string_lossy().to_string(),
+        total: listing.entries.len(),
+        next_offset: (end < listing.entries.len()).then_some(end),
+        truncated: listing.truncated,
+        files,
+    })
+}
 
-    Ok(ProjectFilesResult {
-        root,
-        files: entries,
+/// 模糊搜尋專案檔案
+#[command]
+pub async fn search_project_files(
+    queue: State<'_, RequestQueue>,
+    index: State<'_, FileIndex>,
+    request_id: Option<String>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<ProjectFilesSearch, String> {
+    let root = project_root(&queue, request_id)?;
+    let config = Config::load().files;
+    let limit = limit.unwrap_or(SEARCH_LIMIT);
+
+    let index = index.inner().clone();
+    let search_root = root.clone();
+    let files = tauri::async_runtime::spawn_blocking(move || {
+        index.search(&search_root, &config, &query, limit)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| format!("搜尋專案檔案失敗: {}", e))?;
+
+    Ok(ProjectFilesSearch {
+        root: root.to_string_lossy().to_string(),
+        files,
     })
 }
 
-fn scan_directory(dir: &Path, max_depth: usize) -> std::io::Result<Vec<FileEntry>> {
-    if max_depth == 0 {
-        return Ok(Vec::new());
-    }
+/// 模糊搜尋專案中的符號（Rust、TypeScript/JavaScript、Python）
+#[command]
+pub async fn search_symbols(
+    queue: State<'_, RequestQueue>,
+    index: State<'_, FileIndex>,
+    symbol_index: State<'_, SymbolIndex>,
+    request_id: Option<String>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<SymbolSearch, String> {
+    let root = project_root(&queue, request_id)?;
+    let config = Config::load().files;
+    let limit = limit.unwrap_or(SEARCH_LIMIT);
+
+    let index = index.inner().clone();
+    let symbol_index = symbol_index.inner().clone();
+    let search_root = root.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let listing = index.get(&search_root, &config, false)?;
+        anyhow::Ok(symbol_index.search(&listing, &query, limit))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| format!("搜尋符號失敗: {}", e))?;
+
+    Ok(SymbolSearch {
+        root: root.to_string_lossy().to_string(),
+        symbols: result.matches,
+        indexing: result.indexing,
+    })
+}
 
-    let mut entries = Vec::new();
-
-    for entry in std::fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-
-        // 跳過隱藏目錄和常見的忽略目錄
-        if let Some(name) = path.file_name() {
-            let name_str = name.to_string_lossy();
-            if name_str.starts_with('.') ||
-               name_str == "node_modules" ||
-               name_str == "target" ||
-               name_str == "dist" ||
-               name_str == "build" {
-                continue;
-            }
-        }
+/// 預覽專案內的檔案（大小受限，偵測二進位檔）或資料夾內容
+#[command]
+pub async fn preview_file(
+    queue: State<'_, RequestQueue>,
+    request_id: Option<String>,
+    path: String,
+) -> Result<FilePreview, String> {
+    let root = project_root(&queue, request_id)?;
+    tauri::async_runtime::spawn_blocking(move || files::preview(&root, Path::new(&path)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("預覽失敗: {}", e))
+}
 
-        let name = path.file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_default();
-
-        if path.is_file() {
-            if let Some(path_str) = path.to_str() {
-                entries.push(FileEntry {
-                    path: path_str.to_string(),
-                    name,
-                    is_directory: false,
-                });
-            }
-        } else if path.is_dir() {
-            // 先加入資料夾本身
-            if let Some(path_str) = path.to_str() {
-                entries.push(FileEntry {
-                    path: path_str.to_string(),
-                    name,
-                    is_directory: true,
-                });
-            }
-            // 遞迴掃描子目錄
-            entries.extend(scan_directory(&path, max_depth - 1)?);
-        }
+/// 附加檔案（拖放的路徑），專案內的檔案只回傳路徑
+///
+/// 無法附加的檔案（不存在、超過上限）會略過並回傳錯誤，其餘檔案仍會加入。
+#[command]
+pub async fn add_attachments(
+    queue: State<'_, RequestQueue>,
+    attachments: State<'_, AttachmentStore>,
+    request_id: Option<String>,
+    paths: Vec<String>,
+) -> Result<AttachmentList, String> {
+    let id = pending_request_id(&queue, request_id)?;
+    let root = project_root(&queue, Some(id.clone())).ok();
+    let config = Config::load().attachments;
+
+    let store = attachments.inner().clone();
+    let (request, limits) = (id.clone(), config.clone());
+    let errors = tauri::async_runtime::spawn_blocking(move || {
+        paths
+            .iter()
+            .filter_map(|path| {
+                store
+                    .add_file(&request, root.as_deref(), Path::new(path), &limits)
+                    .err()
+                    .map(|e| format!("{}: {}", path, e))
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
     }
+    Ok(attachments.list(&id, &config))
+}
+
+/// 附加貼上或從檔案選擇器讀取的內容（base64）
+#[command]
+pub fn add_attachment_data(
+    queue: State<'_, RequestQueue>,
+    attachments: State<'_, AttachmentStore>,
+    request_id: Option<String>,
+    name: String,
+    data: String,
+) -> Result<AttachmentList, String> {
+    let id = pending_request_id(&queue, request_id)?;
+    let config = Config::load().attachments;
+    attachments
+        .add_base64(&id, &name, &data, &config)
+        .map_err(|e| format!("{:#}", e))?;
+    Ok(attachments.list(&id, &config))
+}
+
+#[command]
+pub fn remove_attachment(
+    queue: State<'_, RequestQueue>,
+    attachments: State<'_, AttachmentStore>,
+    request_id: Option<String>,
+    attachment_id: String,
+) -> Result<AttachmentList, String> {
+    let id = pending_request_id(&queue, request_id)?;
+    attachments.remove(&id, &attachment_id);
+    Ok(attachments.list(&id, &Config::load().attachments))
+}
+
+/// 請求目前的附件與預算使用量
+#[command]
+pub fn list_attachments(
+    queue: State<'_, RequestQueue>,
+    attachments: State<'_, AttachmentStore>,
+    request_id: Option<String>,
+) -> Result<AttachmentList, String> {
+    let id = pending_request_id(&queue, request_id)?;
+    Ok(attachments.list(&id, &Config::load().attachments))
+}
+
+/// 從系統剪貼簿讀取文字（WebView 取不到剪貼簿內容時使用）
+#[command]
+pub async fn read_clipboard_text(clipboard: State<'_, Clipboard>) -> Result<Option<String>, String> {
+    let clipboard = clipboard.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || clipboard.read_text())
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// 把系統剪貼簿中的圖片（例如截圖）加入附件，剪貼簿中沒有圖片時回傳 None
+#[command]
+pub async fn paste_clipboard_image(
+    queue: State<'_, RequestQueue>,
+    attachments: State<'_, AttachmentStore>,
+    clipboard: State<'_, Clipboard>,
+    request_id: Option<String>,
+) -> Result<Option<AttachmentList>, String> {
+    let id = pending_request_id(&queue, request_id)?;
+    let clipboard = clipboard.inner().clone();
+    let png = tauri::async_runtime::spawn_blocking(move || clipboard.read_image_png())
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    let Some(png) = png else {
+        return Ok(None);
+    };
+
+    let config = Config::load().attachments;
+    let name = format!("clipboard-{}.png", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    attachments
+        .add_data(&id, &name, &png, &config)
+        .map_err(|e| e.to_string())?;
+    Ok(Some(attachments.list(&id, &config)))
+}
+
+/// 複製文字（例如渲染後的摘要 Markdown）到系統剪貼簿
+#[command]
+pub async fn copy_to_clipboard(clipboard: State<'_, Clipboard>, text: String) -> Result<(), String> {
+    let clipboard = clipboard.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || clipboard.write_text(&text))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
 
-    Ok(entries)
+/// 把送出後會回傳給 Claude 的回應 JSON 複製到剪貼簿（不送出、不保存附件）
+#[command]
+pub async fn copy_response_json(
+    queue: State<'_, RequestQueue>,
+    index: State<'_, FileIndex>,
+    symbol_index: State<'_, SymbolIndex>,
+    attachments: State<'_, AttachmentStore>,
+    clipboard: State<'_, Clipboard>,
+    request_id: Option<String>,
+    mut response: UserResponse,
+) -> Result<(), String> {
+    let id = pending_request_id(&queue, request_id)?;
+    response.attachments = attachments.get(&id);
+    let response = resolve_response(&queue, &index, &symbol_index, &id, response, false).await?;
+    let json = serde_json::to_string_pretty(&response).map_err(|e| e.to_string())?;
+    copy_to_clipboard(clipboard, json).await
+}
+
+/// 確認請求仍在等待回應，未指定時使用目前顯示中的請求
+fn pending_request_id(queue: &RequestQueue, request_id: Option<String>) -> Result<String, String> {
+    let id = request_id
+        .or_else(|| queue.active_id())
+        .ok_or("沒有等待回應的請求")?;
+    match queue.request(&id) {
+        Some(_) => Ok(id),
+        None => Err(format!("找不到請求: {}", id)),
+    }
 }
 
 #[command]
@@ -198,126 +560,132 @@ pub async fn open_devtools(app_handle: AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+/// 依 `update.channel` 檢查是否有比目前版本新的 release（以 semver 比較）
 #[command]
 pub async fn check_latest_version() -> Result<VersionInfo, String> {
-    let current_version = env!("CARGO_PKG_VERSION").to_string();
-
-    // 建立 HTTP 客戶端
-    let client = reqwest::Client::builder()
-        .user_agent("claude-confirm/0.1.0")
-        .timeout(std::time::Duration::from_secs(5))
-        .build()
-        .map_err(|e| format!("建立 HTTP 客戶端失敗: {}", e))?;
-
-    // 獲取最新版本資訊
-    match client
-        .get("https://api.github.com/repos/zoonderkins/claude-confirm/releases/latest")
-        .send()
-        .await
-    {
-        Ok(response) => {
-            let status = response.status();
-
-            // 檢查 404 - 代表還沒有 release
-            if status == reqwest::StatusCode::NOT_FOUND {
-                return Ok(VersionInfo {
-                    current: current_version,
-                    latest: None,
-                    has_update: false,
-                    error: Some("GitHub 尚未發布任何 release 版本".to_string()),
-                });
-            }
-
-            // 檢查其他錯誤狀態
-            if !status.is_success() {
-                return Ok(VersionInfo {
-                    current: current_version,
-                    latest: None,
-                    has_update: false,
-                    error: Some(format!("GitHub API 返回錯誤: {}", status)),
-                });
-            }
-
-            match response.json::<GitHubRelease>().await {
-                Ok(release) => {
-                    let latest = release.tag_name.trim_start_matches('v').to_string();
-                    let current = current_version.trim_start_matches('v');
-                    let has_update = latest != current;
-
-                    Ok(VersionInfo {
-                        current: current_version,
-                        latest: Some(latest),
-                        has_update,
-                        error: None,
-                    })
-                }
-                Err(e) => Ok(VersionInfo {
-                    current: current_version,
-                    latest: None,
-                    has_update: false,
-                    error: Some(format!("解析 JSON 失敗: {}", e)),
-                })
-            }
+    let config = Config::load().update;
+    let channel = config.channel;
+
+    match update::check(&config).await {
+        Ok(check) => {
+            let has_update = check.has_update();
+            let latest = check.latest.as_ref();
+            Ok(VersionInfo {
+                current: check.current.to_string(),
+                latest: latest.map(|(version, _)| version.to_string()),
+                has_update,
+                prerelease: latest
+                    .is_some_and(|(version, release)| release.is_prerelease(version)),
+                url: latest.and_then(|(_, release)| release.html_url.clone()),
+                channel,
+                rollback_version: update::rollback_info().map(|manifest| manifest.version),
+                error: match latest {
+                    Some(_) => None,
+                    None => Some("目前的更新頻道沒有可用的 release 版本".to_string()),
+                },
+            })
         }
         Err(e) => Ok(VersionInfo {
-            current: current_version,
+            current: env!("CARGO_PKG_VERSION").to_string(),
             latest: None,
             has_update: false,
-            error: Some(format!("無法連接到 GitHub: {}", e)),
-        })
+            prerelease: false,
+            url: None,
+            channel,
+            rollback_version: update::rollback_info().map(|manifest| manifest.version),
+            error: Some(format!("{:#}", e)),
+        }),
     }
 }
 
+/// 下載並安裝最新版本（同時替換 CLI 與 UI），回傳結果描述
 #[command]
-pub async fn open_github_repo() -> Result<(), String> {
-    let url = "https://github.com/zoonderkins/claude-confirm";
+pub async fn self_update() -> Result<String, String> {
+    let config = Config::load().update;
+    match update::self_update(&config, false).await {
+        Ok(Some(outcome)) => Ok(format!(
+            "已從 {} 更新到 {}，重新啟動後生效",
+            outcome.from, outcome.to
+        )),
+        Ok(None) => Ok(format!("已是最新版本 {}", update::current_version())),
+        Err(e) => Err(format!("更新失敗: {:#}", e)),
+    }
+}
+
+/// 還原到上一次更新前的版本
+#[command]
+pub async fn rollback_update() -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(update::rollback)
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|manifest| format!("已還原到 {}，重新啟動後生效", manifest.version))
+        .map_err(|e| format!("還原失敗: {:#}", e))
+}
+
+/// 沒有 release 頁面或頁面網址不可信時開啟的專案首頁
+const REPO_URL: &str = "https://github.com/zoonderkins/claude-confirm";
 
-    // 使用系統預設瀏覽器開啟 URL
+/// 開啟 GitHub 專案頁面，指定 `url`（release 頁面）時開啟該網址
+///
+/// `url` 來自（可自訂的）release 列表，只接受位於更新來源主機的 https 網址，其餘改開專案首頁。
+#[command]
+pub async fn open_github_repo(url: Option<String>) -> Result<(), String> {
+    let config = Config::load().update;
+    let target = url
+        .and_then(|url| update::release_page_url(&config, &url))
+        .map(String::from)
+        .unwrap_or_else(|| REPO_URL.to_string());
+    open_in_system(&target)
+}
+
+/// 使用系統預設程式開啟 URL 或路徑
+///
+/// Windows 上交給 `explorer` 開啟，不經過 `cmd`，網址中的 `&`、`|` 等字元不會被當成指令。
+pub(crate) fn open_in_system(target: &str) -> Result<(), String> {
     #[cfg(target_os = "macos")]
     {
         std::process::Command::new("open")
-            .arg(url)
+            .arg(target)
             .spawn()
-            .map_err(|e| format!("開啟瀏覽器失敗: {}", e))?;
+            .map_err(|e| format!("開啟 {} 失敗: {}", target, e))?;
     }
 
     #[cfg(target_os = "linux")]
     {
         std::process::Command::new("xdg-open")
-            .arg(url)
+            .arg(target)
             .spawn()
-            .map_err(|e| format!("開啟瀏覽器失敗: {}", e))?;
+            .map_err(|e| format!("開啟 {} 失敗: {}", target, e))?;
     }
 
     #[cfg(target_os = "windows")]
     {
-        std::process::Command::new("cmd")
-            .args(&["/C", "start", url])
+        std::process::Command::new("explorer")
+            .arg(target)
             .spawn()
-            .map_err(|e| format!("開啟瀏覽器失敗: {}", e))?;
+            .map_err(|e| format!("開啟 {} 失敗: {}", target, e))?;
     }
 
     Ok(())
 }
 
+/// 把前端產生的匯出檔（base64）寫入匯出目錄，回傳實際寫入的路徑
+///
+/// 檔名會經過清理、副檔名須符合 `file_type`，同名檔案不會被覆蓋。
 #[command]
 pub async fn save_export_file(filename: String, data: String, file_type: String) -> Result<String, String> {
-    // 取得 Downloads 目錄
-    let downloads_dir = dirs::download_dir()
-        .ok_or("無法取得 Downloads 目錄")?;
-
-    let file_path = downloads_dir.join(&filename);
-
-    // 解碼 base64 資料
     let decoded = general_purpose::STANDARD
         .decode(&data)
         .map_err(|e| format!("Base64 解碼失敗: {}", e))?;
 
-    // 寫入檔案
-    std::fs::write(&file_path, decoded)
-        .map_err(|e| format!("寫入檔案失敗: {}", e))?;
-
-    log::info!("匯出檔案至: {:?} (類型: {})", file_path, file_type);
+    let config = Config::load().export;
+    let path = tauri::async_runtime::spawn_blocking(move || {
+        export::save(&config, &filename, &file_type, &decoded)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| format!("寫入檔案失敗: {}", e))?;
 
-    Ok(file_path.to_string_lossy().to_string())
+    log::info!("匯出檔案至: {:?}", path);
+    Ok(path.to_string_lossy().to_string())
 }
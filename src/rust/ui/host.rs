@@ -0,0 +1,365 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, UserAttentionType};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+use crate::files::AttachmentStore;
+use crate::popup::{host_file, HostAck, HostInfo, HostRequest};
+use crate::types::{PopupRequest, UserResponse};
+
+/// 回應送回給誰
+enum Responder {
+    /// 單次模式：輸出到 stdout，由 MCP 服務器讀取
+    Stdout,
+    /// host 模式：送回等待中的 TCP 連線
+    Channel(oneshot::Sender<UserResponse>),
+}
+
+struct PendingRequest {
+    request: PopupRequest,
+    received_at: DateTime<Local>,
+    responder: Responder,
+}
+
+/// 給前端與托盤顯示的待處理請求
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingSummary {
+    pub request: PopupRequest,
+    pub received_at: DateTime<Local>,
+}
+
+/// 佇列目前狀態
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueSnapshot {
+    pub requests: Vec<PendingSummary>,
+    /// 目前顯示中的請求
+    pub active: Option<String>,
+    pub do_not_disturb: bool,
+    /// 是否為常駐 host（false 代表單次模式，回應後即結束）
+    pub host_mode: bool,
+}
+
+#[derive(Default)]
+struct QueueState {
+    pending: Vec<PendingRequest>,
+    active: Option<String>,
+    do_not_disturb: bool,
+    host_mode: bool,
+}
+
+/// 完成一個請求後的結果
+pub struct Completed {
+    pub request: PopupRequest,
+    /// 單次模式的請求完成後應結束程序
+    pub exit: bool,
+}
+
+/// UI 中等待回應的請求佇列
+#[derive(Default)]
+pub struct RequestQueue {
+    state: Mutex<QueueState>,
+}
+
+impl RequestQueue {
+    /// 常駐 host 模式的空佇列
+    pub fn host() -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                host_mode: true,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// 單次模式：只處理 `--mcp-request` 指定的請求
+    pub fn standalone(request: Option<PopupRequest>) -> Self {
+        let queue = Self::default();
+        if let Some(request) = request {
+            queue.push(request, Responder::Stdout);
+        }
+        queue
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        // 其他執行緒 panic 時仍繼續使用佇列
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn push(&self, request: PopupRequest, responder: Responder) {
+        let mut state = self.lock();
+        if state.active.is_none() {
+            state.active = Some(request.id.clone());
+        }
+        state.pending.push(PendingRequest {
+            request,
+            received_at: Local::now(),
+            responder,
+        });
+    }
+
+    /// 送出回應並移出佇列
+    pub fn complete(&self, id: &str, response: UserResponse) -> Option<Completed> {
+        let pending = self.take(id)?;
+        let exit = match pending.responder {
+            Responder::Stdout => {
+                // 將結果輸出到 stdout
+                if let Ok(json) = serde_json::to_string(&response) {
+                    println!("{}", json);
+                }
+                true
+            }
+            Responder::Channel(sender) => {
+                let _ = sender.send(response);
+                false
+            }
+        };
+        Some(Completed {
+            request: pending.request,
+            exit,
+        })
+    }
+
+    /// 撤回請求（等待端已斷線），回傳是否確實移除
+    fn withdraw(&self, id: &str) -> bool {
+        self.take(id).is_some()
+    }
+
+    fn take(&self, id: &str) -> Option<PendingRequest> {
+        let mut state = self.lock();
+        let index = state.pending.iter().position(|p| p.request.id == id)?;
+        let pending = state.pending.remove(index);
+        if state.active.as_deref() == Some(id) {
+            state.active = state.pending.first().map(|p| p.request.id.clone());
+        }
+        Some(pending)
+    }
+
+    pub fn set_active(&self, id: &str) -> bool {
+        let mut state = self.lock();
+        if state.pending.iter().any(|p| p.request.id == id) {
+            state.active = Some(id.to_string());
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 取得等待中的請求
+    pub fn request(&self, id: &str) -> Option<PopupRequest> {
+        self.lock()
+            .pending
+            .iter()
+            .find(|p| p.request.id == id)
+            .map(|p| p.request.clone())
+    }
+
+    pub fn active_id(&self) -> Option<String> {
+        self.lock().active.clone()
+    }
+
+    pub fn set_do_not_disturb(&self, enabled: bool) {
+        self.lock().do_not_disturb = enabled;
+    }
+
+    pub fn snapshot(&self) -> QueueSnapshot {
+        let state = self.lock();
+        QueueSnapshot {
+            requests: state
+                .pending
+                .iter()
+                .map(|p| PendingSummary {
+                    request: p.request.clone(),
+                    received_at: p.received_at,
+                })
+                .collect(),
+            active: state.active.clone(),
+            do_not_disturb: state.do_not_disturb,
+            host_mode: state.host_mode,
+        }
+    }
+
+    /// 取消所有等待中的請求（結束程序前）
+    pub fn cancel_all(&self) {
+        let ids: Vec<String> = self.lock().pending.iter().map(|p| p.request.id.clone()).collect();
+        for id in ids {
+            self.complete(&id, UserResponse::cancelled());
+        }
+    }
+}
+
+/// 佇列變動後同步前端、托盤與視窗
+///
+/// `reveal` 為 true（有新請求）且未開啟勿擾模式時，顯示並聚焦視窗；
+/// 視窗已顯示時只發出提示，避免打斷正在處理的請求。
+pub fn on_queue_changed(app: &AppHandle, reveal: bool) {
+    let snapshot = app.state::<RequestQueue>().snapshot();
+    let _ = app.emit("queue-updated", &snapshot);
+    super::tray::refresh(app, &snapshot);
+
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+
+    let count = snapshot.requests.len();
+    let title = if count > 1 {
+        format!("Claude Confirm ({} 個待處理)", count)
+    } else {
+        "Claude Confirm".to_string()
+    };
+    let _ = window.set_title(&title);
+    let _ = window.set_badge_count((count > 0).then_some(count as i64));
+
+    if snapshot.host_mode && count == 0 {
+        let _ = window.hide();
+    } else if reveal && !snapshot.do_not_disturb {
+        if window.is_visible().unwrap_or(false) {
+            // 視窗已在處理其他請求，只提示不搶焦點，新請求出現在側欄
+            let _ = window.request_user_attention(Some(UserAttentionType::Informational));
+        } else {
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+    }
+}
+
+/// 取得 host 的獨佔鎖（`ui-host.lock`），已有其他 host 在執行或啟動中時回傳 None
+///
+/// 鎖在回傳的檔案關閉或程序結束時釋放，因此 host 當掉也不會留下過期的鎖。
+pub fn lock_host() -> Result<Option<std::fs::File>> {
+    let path = host_file()
+        .context("無法取得 host 資訊檔位置")?
+        .with_file_name("ui-host.lock");
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .with_context(|| format!("無法開啟 {}", path.display()))?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(std::fs::TryLockError::WouldBlock) => Ok(None),
+        Err(std::fs::TryLockError::Error(e)) => {
+            Err(e).with_context(|| format!("無法鎖定 {}", path.display()))
+        }
+    }
+}
+
+/// 啟動 host 監聽，接收 MCP 服務器送來的請求
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = listen(app).await {
+            log::error!("UI host 監聽失敗: {:#}", e);
+        }
+    });
+}
+
+/// 結束前移除連線資訊檔（只移除自己寫的）
+pub fn remove_host_file() {
+    let Some(path) = host_file() else {
+        return;
+    };
+    let is_ours = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<HostInfo>(&content).ok())
+        .is_some_and(|info| info.pid == std::process::id());
+    if is_ours {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+async fn listen(app: AppHandle) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+    let info = HostInfo {
+        port: listener.local_addr()?.port(),
+        pid: std::process::id(),
+        token: uuid::Uuid::new_v4().to_string(),
+    };
+    write_host_file(&info)?;
+    log::info!("UI host 監聽 127.0.0.1:{}", info.port);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let app = app.clone();
+        let token = info.token.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = handle_connection(app, stream, &token).await {
+                log::warn!("處理 host 連線失敗: {:#}", e);
+            }
+        });
+    }
+}
+
+/// 一個連線對應一個請求：收到請求後等待用戶回應，或等待端斷線時撤回
+async fn handle_connection(app: AppHandle, stream: TcpStream, token: &str) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    let line = lines.next_line().await?.context("連線未送出請求")?;
+    let message: HostRequest = serde_json::from_str(&line).context("解析請求失敗")?;
+    anyhow::ensure!(message.token == token, "token 不符，拒絕請求");
+
+    let id = message.request.id.clone();
+    let ack = HostAck { accepted: id.clone() };
+    writer
+        .write_all(format!("{}\n", serde_json::to_string(&ack)?).as_bytes())
+        .await?;
+
+    let (sender, receiver) = oneshot::channel();
+    app.state::<RequestQueue>()
+        .push(message.request, Responder::Channel(sender));
+    on_queue_changed(&app, true);
+
+    tokio::select! {
+        response = receiver => {
+            if let Ok(response) = response {
+                writer
+                    .write_all(format!("{}\n", serde_json::to_string(&response)?).as_bytes())
+                    .await?;
+            }
+        }
+        // 等待端逾時或 MCP 服務器結束
+        _ = lines.next_line() => {
+            if app.state::<RequestQueue>().withdraw(&id) {
+                log::info!("請求 {} 的等待端已斷線，撤回請求", id);
+                app.state::<AttachmentStore>().take(&id);
+                on_queue_changed(&app, false);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_host_file(info: &HostInfo) -> Result<()> {
+    let path = host_file().context("無法取得 host 資訊檔位置")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    use std::io::Write;
+    let mut file = options.open(&path)?;
+    // `mode` 只在建立檔案時生效，既有的檔案要明確改權限後才寫入 token
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(serde_json::to_string(info)?.as_bytes())?;
+    Ok(())
+}
@@ -1,40 +1,131 @@
+mod clipboard;
 mod commands;
+mod host;
+mod refocus;
+mod tray;
 
 pub use commands::*;
 
 use tauri::{Manager, WindowEvent};
 
+use crate::files::{AttachmentStore, FileIndex, SymbolIndex};
+use crate::types::PopupRequest;
+use clipboard::Clipboard;
+use host::RequestQueue;
+
 pub fn run_ui_app() {
+    let args: Vec<String> = std::env::args().collect();
+
+    // X11 上由這個子程序持有複製的內容，UI 結束後仍可貼上
+    if args.iter().any(|arg| arg == clipboard::SERVE_ARG) {
+        if let Err(e) = clipboard::serve() {
+            log::error!("持有剪貼簿失敗: {:#}", e);
+            std::process::exit(1);
+        }
+        std::process::exit(0);
+    }
+
+    let host_mode = args.iter().any(|arg| arg == "--host");
+
+    // 同時只能有一個 host：先取得獨佔鎖才監聽與寫入連線資訊，鎖在程序結束時釋放
+    let _host_lock = if host_mode {
+        match host::lock_host() {
+            Ok(Some(lock)) => Some(lock),
+            // 已有常駐 host 在執行或啟動中，新請求會送到那裡
+            Ok(None) => std::process::exit(0),
+            Err(e) => {
+                log::error!("無法取得 UI host 鎖: {:#}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    let queue = if host_mode {
+        RequestQueue::host()
+    } else {
+        RequestQueue::standalone(read_request_arg(&args))
+    };
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
+        .manage(queue)
+        .manage(FileIndex::default())
+        .manage(SymbolIndex::default())
+        .manage(AttachmentStore::default())
+        .manage(Clipboard::default())
         .invoke_handler(tauri::generate_handler![
             commands::submit_response,
             commands::cancel_response,
+            commands::get_pending_requests,
+            commands::set_active_request,
             commands::get_project_files,
             commands::get_project_files_with_root,
+            commands::list_project_files,
+            commands::search_project_files,
+            commands::search_symbols,
+            commands::preview_file,
+            commands::add_attachments,
+            commands::add_attachment_data,
+            commands::remove_attachment,
+            commands::list_attachments,
+            commands::read_clipboard_text,
+            commands::paste_clipboard_image,
+            commands::copy_to_clipboard,
+            commands::copy_response_json,
             commands::get_cli_args,
             commands::read_mcp_request,
             commands::set_always_on_top,
             commands::open_devtools,
             commands::check_latest_version,
+            commands::self_update,
+            commands::rollback_update,
             commands::open_github_repo,
             commands::save_export_file,
         ])
-        .setup(|app| {
+        .setup(move |app| {
             let window = app.get_webview_window("main").unwrap();
-            let _ = window.show();
-            let _ = window.set_focus();
+
+            if host_mode {
+                // 常駐在托盤，有請求時才顯示視窗
+                let _ = window.hide();
+                tray::create(app.handle())?;
+                host::start(app.handle().clone());
+            } else {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
 
             Ok(())
         })
-        .on_window_event(|_window, event| {
-            if let WindowEvent::CloseRequested { .. } = event {
-                // 窗口關閉時，輸出取消回應
-                let response = crate::types::UserResponse::cancelled();
-                println!("{}", serde_json::to_string(&response).unwrap());
-                std::process::exit(0);
+        .on_window_event(|window, event| {
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                let queue = window.state::<RequestQueue>();
+                if queue.snapshot().host_mode {
+                    // host 模式下關閉視窗只是隱藏，請求仍留在托盤
+                    api.prevent_close();
+                    let _ = window.hide();
+                } else {
+                    // 窗口關閉時，輸出取消回應
+                    queue.cancel_all();
+                }
             }
         })
         .run(tauri::generate_context!())
         .expect("運行 Tauri 應用失敗");
+
+    host::remove_host_file();
+}
+
+/// 讀取 `--mcp-request <file>` 指定的請求
+fn read_request_arg(args: &[String]) -> Option<PopupRequest> {
+    let index = args.iter().position(|arg| arg == "--mcp-request")?;
+    let path = args.get(index + 1)?;
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| log::error!("讀取請求文件失敗: {}", e))
+        .ok()?;
+    serde_json::from_str(&content)
+        .map_err(|e| log::error!("解析請求失敗: {}", e))
+        .ok()
 }
@@ -0,0 +1,117 @@
+use std::process::{Command, Stdio};
+
+use crate::config::RefocusConfig;
+use crate::types::EnvContext;
+
+/// 把焦點還給發出請求的終端機（依用戶設定）
+pub fn refocus_terminal(config: &RefocusConfig, env: &EnvContext) {
+    if !config.enabled {
+        return;
+    }
+
+    if config.tmux {
+        refocus_tmux(env);
+    }
+
+    #[cfg(target_os = "linux")]
+    if config.x11 {
+        refocus_x11(env);
+    }
+}
+
+/// 切換到請求來源的 tmux window 與 pane
+fn refocus_tmux(env: &EnvContext) {
+    let Some(tmux) = env.session.as_ref().and_then(|s| s.tmux.as_ref()) else {
+        return;
+    };
+
+    let run = |args: &[&str]| {
+        let mut command = Command::new("tmux");
+        if let Some(socket) = &tmux.socket {
+            command.arg("-S").arg(socket);
+        }
+        let ok = command
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success());
+        if !ok {
+            log::debug!("tmux {:?} 失敗", args);
+        }
+    };
+
+    run(&["select-window", "-t", &tmux.pane_id]);
+    run(&["select-pane", "-t", &tmux.pane_id]);
+}
+
+/// 在 X11 下依進程鏈找到終端機視窗並啟用
+///
+/// 從 MCP 服務器進程往上找，第一個擁有視窗（_NET_WM_PID）的進程通常就是終端機。
+#[cfg(target_os = "linux")]
+fn refocus_x11(env: &EnvContext) {
+    if std::env::var_os("DISPLAY").is_none() {
+        return;
+    }
+    let Some(session) = env.session.as_ref() else {
+        return;
+    };
+    // 透過 SSH 發出的請求，進程不在本機的視窗系統中
+    if session.ssh.is_some() {
+        return;
+    }
+
+    let pids = env
+        .pid
+        .into_iter()
+        .chain(session.process_chain.iter().map(|p| p.pid));
+
+    for pid in pids {
+        if let Some(window) = find_window(pid) {
+            let activated = Command::new("xdotool")
+                .args(["windowactivate", &window])
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|s| s.success())
+                || Command::new("wmctrl")
+                    .args(["-i", "-a", &window])
+                    .stderr(Stdio::null())
+                    .status()
+                    .is_ok_and(|s| s.success());
+            if activated {
+                log::info!("已返回終端機視窗 {} (pid {})", window, pid);
+            }
+            return;
+        }
+    }
+}
+
+/// 找到屬於 `pid` 的 X11 視窗 ID
+#[cfg(target_os = "linux")]
+fn find_window(pid: u32) -> Option<String> {
+    let pid = pid.to_string();
+
+    // xdotool search --pid <pid>
+    if let Ok(output) = Command::new("xdotool")
+        .args(["search", "--onlyvisible", "--pid", &pid])
+        .stderr(Stdio::null())
+        .output()
+    {
+        if let Some(window) = String::from_utf8_lossy(&output.stdout).lines().next() {
+            return Some(window.trim().to_string());
+        }
+    }
+
+    // wmctrl -lp：<window id> <desktop> <pid> <host> <title>
+    let output = Command::new("wmctrl")
+        .arg("-lp")
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let window = fields.next()?;
+        let window_pid = fields.nth(1)?;
+        (window_pid == pid).then(|| window.to_string())
+    })
+}
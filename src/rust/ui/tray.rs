@@ -0,0 +1,158 @@
+use chrono::Local;
+use tauri::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager};
+
+use super::host::{self, PendingSummary, QueueSnapshot, RequestQueue};
+use crate::files::AttachmentStore;
+use crate::types::UserResponse;
+
+const TRAY_ID: &str = "main";
+/// 有待處理請求時重建選單的間隔，讓「N 分鐘前」保持正確
+const AGE_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// 建立托盤圖示（僅 host 模式）
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let snapshot = app.state::<RequestQueue>().snapshot();
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&build_menu(app, &snapshot)?)
+        .tooltip("Claude Confirm")
+        .on_menu_event(handle_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(AGE_REFRESH_INTERVAL).await;
+            let snapshot = app.state::<RequestQueue>().snapshot();
+            if !snapshot.requests.is_empty() {
+                refresh(&app, &snapshot);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// 依佇列狀態更新選單與待處理數量
+pub fn refresh(app: &AppHandle, snapshot: &QueueSnapshot) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+
+    if let Ok(menu) = build_menu(app, snapshot) {
+        let _ = tray.set_menu(Some(menu));
+    }
+
+    let count = snapshot.requests.len();
+    let _ = tray.set_tooltip(Some(format!("Claude Confirm - {} 個待處理請求", count)));
+    // macOS 會在圖示旁顯示文字，作為待處理數量的 badge
+    let _ = tray.set_title((count > 0).then(|| count.to_string()));
+}
+
+fn build_menu(app: &AppHandle, snapshot: &QueueSnapshot) -> tauri::Result<Menu<tauri::Wry>> {
+    let menu = Menu::new(app)?;
+
+    if snapshot.requests.is_empty() {
+        menu.append(&MenuItem::with_id(app, "empty", "沒有待處理的請求", false, None::<&str>)?)?;
+    }
+
+    for pending in &snapshot.requests {
+        let id = &pending.request.id;
+        let submenu = Submenu::with_items(
+            app,
+            request_label(pending),
+            true,
+            &[
+                &MenuItem::with_id(app, format!("open:{}", id), "開啟", true, None::<&str>)?,
+                &MenuItem::with_id(app, format!("dismiss:{}", id), "略過（回覆取消）", true, None::<&str>)?,
+            ],
+        )?;
+        menu.append(&submenu)?;
+    }
+
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&CheckMenuItem::with_id(
+        app,
+        "dnd",
+        "勿擾模式（新請求不彈出視窗）",
+        true,
+        snapshot.do_not_disturb,
+        None::<&str>,
+    )?)?;
+    menu.append(&MenuItem::with_id(app, "history", "開啟歷史紀錄", true, None::<&str>)?)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&MenuItem::with_id(app, "quit", "結束 Claude Confirm", true, None::<&str>)?)?;
+
+    Ok(menu)
+}
+
+/// 例如「📁 my-project · 3 分鐘前 · claude-code」
+fn request_label(pending: &PendingSummary) -> String {
+    let env = pending.request.env_context.as_ref();
+    let project = env
+        .and_then(|e| e.project_name.clone())
+        .unwrap_or_else(|| "未知專案".to_string());
+
+    let minutes = (Local::now() - pending.received_at).num_minutes();
+    let age = if minutes < 1 {
+        "剛剛".to_string()
+    } else if minutes < 60 {
+        format!("{} 分鐘前", minutes)
+    } else {
+        format!("{} 小時前", minutes / 60)
+    };
+
+    match env.and_then(|e| e.client.as_ref()) {
+        Some(client) => format!(
+            "📁 {} · {} · {}",
+            project,
+            age,
+            client.title.as_deref().unwrap_or(&client.name)
+        ),
+        None => format!("📁 {} · {}", project, age),
+    }
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    let queue = app.state::<RequestQueue>();
+
+    match event.id().as_ref() {
+        "dnd" => {
+            let enabled = !queue.snapshot().do_not_disturb;
+            queue.set_do_not_disturb(enabled);
+            host::on_queue_changed(app, false);
+        }
+        "history" => {
+            if let Some(dir) = crate::history::history_dir() {
+                let _ = std::fs::create_dir_all(&dir);
+                if let Err(e) = super::commands::open_in_system(&dir.to_string_lossy()) {
+                    log::warn!("{}", e);
+                }
+            }
+        }
+        "quit" => {
+            queue.cancel_all();
+            host::remove_host_file();
+            app.exit(0);
+        }
+        id => {
+            if let Some(request_id) = id.strip_prefix("open:") {
+                if queue.set_active(request_id) {
+                    host::on_queue_changed(app, false);
+                    if let Some(window) = app.get_webview_window("main") {
+                        let _ = window.show();
+                        let _ = window.set_focus();
+                    }
+                }
+            } else if let Some(request_id) = id.strip_prefix("dismiss:") {
+                app.state::<AttachmentStore>().take(request_id);
+                if queue.complete(request_id, UserResponse::cancelled()).is_some() {
+                    host::on_queue_changed(app, false);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,363 @@
+mod self_update;
+
+pub use self_update::{rollback, rollback_info, self_update, RollbackManifest, UpdateOutcome};
+
+use anyhow::{bail, Context, Result};
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use reqwest::StatusCode;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::UpdateConfig;
+
+/// 送出請求時使用的 User-Agent（GitHub API 要求必須提供）
+pub const USER_AGENT: &str = concat!("claude-confirm/", env!("CARGO_PKG_VERSION"));
+/// 預設的 release 列表（GitHub releases API）
+pub const DEFAULT_ENDPOINT: &str = "https://api.github.com/repos/zoonderkins/claude-confirm/releases";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// release 列表的大小上限
+const MAX_RELEASES_BYTES: u64 = 4 * 1024 * 1024;
+
+/// 更新頻道
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    /// 只考慮正式版
+    #[default]
+    Stable,
+    /// 也包括標記為 prerelease 或版本號帶有 `-beta` 等後綴的預覽版
+    Prerelease,
+}
+
+/// release 列表中的一筆（GitHub releases API 的欄位子集）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
+    /// release 頁面
+    #[serde(default)]
+    pub html_url: Option<String>,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+/// release 附帶的檔案
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+    #[serde(default)]
+    pub size: u64,
+}
+
+impl Release {
+    /// 由 tag 解析版本號（可帶 `v` 前綴），不是 semver 時回傳 None
+    pub fn version(&self) -> Option<Version> {
+        Version::parse(self.tag_name.trim().trim_start_matches('v')).ok()
+    }
+
+    /// 標記為 prerelease，或版本號帶有預覽後綴
+    pub fn is_prerelease(&self, version: &Version) -> bool {
+        self.prerelease || !version.pre.is_empty()
+    }
+
+    pub fn asset(&self, name: &str) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+}
+
+/// 版本檢查結果
+#[derive(Debug, Clone)]
+pub struct UpdateCheck {
+    pub current: Version,
+    /// 頻道中最新的 release，沒有任何可用的 release 時為 None
+    pub latest: Option<(Version, Release)>,
+}
+
+impl UpdateCheck {
+    /// 最新 release 比目前版本新（本機版本較新時不算）
+    pub fn has_update(&self) -> bool {
+        self.latest
+            .as_ref()
+            .is_some_and(|(version, _)| *version > self.current)
+    }
+}
+
+/// 目前執行檔的版本
+pub fn current_version() -> Version {
+    Version::parse(env!("CARGO_PKG_VERSION")).expect("CARGO_PKG_VERSION 必須是 semver")
+}
+
+/// 取得 release 列表，找出頻道中最新的版本
+pub async fn check(config: &UpdateConfig) -> Result<UpdateCheck> {
+    let releases = fetch_releases(config).await?;
+    Ok(UpdateCheck {
+        current: current_version(),
+        latest: latest(releases, config.channel),
+    })
+}
+
+/// 依 semver 找出頻道中最新的 release，略過草稿與無法解析版本號的 tag
+pub fn latest(releases: Vec<Release>, channel: UpdateChannel) -> Option<(Version, Release)> {
+    releases
+        .into_iter()
+        .filter(|release| !release.draft)
+        .filter_map(|release| Some((release.version()?, release)))
+        .filter(|(version, release)| {
+            channel == UpdateChannel::Prerelease || !release.is_prerelease(version)
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+}
+
+/// ETag 快取（`<cache_dir>/claude-confirm/releases.json`）
+#[derive(Debug, Serialize, Deserialize)]
+struct ReleaseCache {
+    endpoint: String,
+    etag: String,
+    body: String,
+}
+
+fn cache_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("claude-confirm").join("releases.json"))
+}
+
+fn load_cache(endpoint: &str) -> Option<ReleaseCache> {
+    let content = std::fs::read_to_string(cache_path()?).ok()?;
+    let cache: ReleaseCache = serde_json::from_str(&content).ok()?;
+    (cache.endpoint == endpoint).then_some(cache)
+}
+
+fn save_cache(cache: &ReleaseCache) -> Result<()> {
+    let path = cache_path().context("無法取得快取目錄")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, serde_json::to_string(cache)?)?;
+    Ok(())
+}
+
+/// release 頁面所在的主機
+///
+/// GitHub releases API（`api.github.com`）的頁面在 `github.com`；自訂 endpoint（內部鏡像、
+/// GitHub Enterprise）的頁面與 endpoint 同主機。
+pub fn release_page_host(config: &UpdateConfig) -> Option<String> {
+    let endpoint = config.endpoint.as_deref().unwrap_or(DEFAULT_ENDPOINT);
+    let host = reqwest::Url::parse(endpoint).ok()?.host_str()?.to_ascii_lowercase();
+    Some(match host.as_str() {
+        "api.github.com" => "github.com".to_string(),
+        _ => host,
+    })
+}
+
+/// 檢查 release 列表中的 `html_url`，只接受位於 [`release_page_host`] 的 https 網址
+pub fn release_page_url(config: &UpdateConfig, url: &str) -> Option<reqwest::Url> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let host = release_page_host(config)?;
+    (url.scheme() == "https" && url.host_str() == Some(host.as_str())).then_some(url)
+}
+
+///
+/// 帶上前一次回應的 ETag，未變更時（304）使用快取，不計入 GitHub API 的速率限制。
+/// 端點可回傳 release 陣列（`/releases`）或單一 release（`/releases/latest`）。
+pub async fn fetch_releases(config: &UpdateConfig) -> Result<Vec<Release>> {
+    let endpoint = config.endpoint.as_deref().unwrap_or(DEFAULT_ENDPOINT);
+    require_https(endpoint).context("update.endpoint 無效")?;
+    let cache = load_cache(endpoint);
+
+    let client = http_client(REQUEST_TIMEOUT)?;
+    let mut request = client
+        .get(endpoint)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json");
+    if let Some(cache) = &cache {
+        request = request.header(IF_NONE_MATCH, &cache.etag);
+    }
+
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("無法連接到 {}", endpoint))?;
+    let status = response.status();
+
+    let body = match (status, cache) {
+        (StatusCode::NOT_MODIFIED, Some(cache)) => cache.body,
+        (StatusCode::NOT_FOUND, _) => bail!("尚未發布任何 release 版本"),
+        (status, _) if !status.is_success() => bail!("release 端點返回錯誤: {}", status),
+        _ => {
+            let etag = response
+                .headers()
+                .get(ETAG)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from);
+            let body = read_limited(response, MAX_RELEASES_BYTES)
+                .await
+                .context("讀取 release 列表失敗")?;
+            let body = String::from_utf8(body).context("release 列表不是 UTF-8 文字")?;
+            if let Some(etag) = etag {
+                let cache = ReleaseCache {
+                    endpoint: endpoint.to_string(),
+                    etag,
+                    body: body.clone(),
+                };
+                if let Err(e) = save_cache(&cache) {
+                    log::debug!("寫入 release 快取失敗: {}", e);
+                }
+            }
+            body
+        }
+    };
+
+    parse_releases(&body)
+}
+
+/// 只允許 https（重新導向到 http 也會失敗）
+fn http_client(timeout: Duration) -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(timeout)
+        .https_only(true)
+        .build()
+        .context("建立 HTTP 客戶端失敗")
+}
+
+/// 網址必須是 https
+fn require_https(url: &str) -> Result<()> {
+    let parsed = reqwest::Url::parse(url).with_context(|| format!("無法解析網址: {}", url))?;
+    if parsed.scheme() != "https" {
+        bail!("只接受 https 網址: {}", url);
+    }
+    Ok(())
+}
+
+/// 讀取回應內容，超過 `limit` bytes 時中止
+async fn read_limited(mut response: reqwest::Response, limit: u64) -> Result<Vec<u8>> {
+    if let Some(length) = response.content_length().filter(|&length| length > limit) {
+        bail!("內容大小 {} bytes 超過上限 {} bytes", length, limit);
+    }
+    let mut data = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if (data.len() + chunk.len()) as u64 > limit {
+            bail!("內容超過上限 {} bytes", limit);
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+fn parse_releases(body: &str) -> Result<Vec<Release>> {
+    let value: serde_json::Value = serde_json::from_str(body).context("解析 JSON 失敗")?;
+    let releases = if value.is_array() {
+        serde_json::from_value(value)?
+    } else {
+        vec![serde_json::from_value(value)?]
+    };
+    Ok(releases)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag: &str, prerelease: bool, draft: bool) -> Release {
+        Release {
+            tag_name: tag.to_string(),
+            name: None,
+            prerelease,
+            draft,
+            html_url: None,
+            assets: Vec::new(),
+        }
+    }
+
+    /// 最新 release 的 tag，沒有時為空字串
+    fn latest_tag(releases: &[Release], channel: UpdateChannel) -> String {
+        latest(releases.to_vec(), channel)
+            .map(|(_, release)| release.tag_name)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn compares_versions_numerically() {
+        let releases = vec![
+            release("v0.9.0", false, false),
+            release("v0.10.0", false, false),
+            release("0.2.0", false, false),
+        ];
+        assert_eq!(latest_tag(&releases, UpdateChannel::Stable), "v0.10.0");
+    }
+
+    #[test]
+    fn stable_channel_skips_prereleases() {
+        let releases = vec![
+            release("v1.0.0", false, false),
+            release("v1.1.0-beta.1", false, false),
+            release("v1.2.0", true, false),
+        ];
+        assert_eq!(latest_tag(&releases, UpdateChannel::Stable), "v1.0.0");
+        assert_eq!(latest_tag(&releases, UpdateChannel::Prerelease), "v1.2.0");
+    }
+
+    #[test]
+    fn prerelease_sorts_before_its_release() {
+        let releases = vec![
+            release("v2.0.0-rc.1", false, false),
+            release("v2.0.0", false, false),
+        ];
+        assert_eq!(latest_tag(&releases, UpdateChannel::Prerelease), "v2.0.0");
+    }
+
+    #[test]
+    fn skips_drafts_and_non_semver_tags() {
+        let releases = vec![
+            release("v3.0.0", false, true),
+            release("nightly", false, false),
+            release("v1.0", false, false),
+            release("v0.5.0", false, false),
+        ];
+        assert_eq!(latest_tag(&releases, UpdateChannel::Stable), "v0.5.0");
+        assert_eq!(latest_tag(&[], UpdateChannel::Stable), "");
+    }
+
+    #[test]
+    fn local_version_newer_than_latest_is_not_an_update() {
+        let check = UpdateCheck {
+            current: Version::new(2, 0, 0),
+            latest: latest(vec![release("v1.9.0", false, false)], UpdateChannel::Stable),
+        };
+        assert!(!check.has_update());
+    }
+
+    #[test]
+    fn release_pages_must_be_on_the_release_host() {
+        let github = UpdateConfig::default();
+        let page = |config: &UpdateConfig, url: &str| {
+            release_page_url(config, url).map(String::from)
+        };
+
+        assert_eq!(
+            page(&github, "https://github.com/zoonderkins/claude-confirm/releases/tag/v1.0.0"),
+            Some("https://github.com/zoonderkins/claude-confirm/releases/tag/v1.0.0".to_string())
+        );
+        assert_eq!(page(&github, "http://github.com/zoonderkins/claude-confirm"), None);
+        assert_eq!(page(&github, "https://github.com.evil.com/"), None);
+        assert_eq!(page(&github, "https://github.com@evil.com/"), None);
+        assert_eq!(page(&github, "https://evil.com/?&calc.exe"), None);
+        assert_eq!(page(&github, "not a url"), None);
+
+        let mirror = UpdateConfig {
+            endpoint: Some(
+                "https://git.example.com/api/v3/repos/team/claude-confirm/releases".into(),
+            ),
+            ..Default::default()
+        };
+        assert!(page(&mirror, "https://git.example.com/team/claude-confirm/releases").is_some());
+        assert_eq!(page(&mirror, "https://github.com/zoonderkins/claude-confirm"), None);
+    }
+}
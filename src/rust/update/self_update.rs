@@ -0,0 +1,437 @@
+use anyhow::{anyhow, bail, Context, Result};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::{check, http_client, read_limited, require_https, Release, ReleaseAsset};
+use crate::config::UpdateConfig;
+
+/// 未設定 `update.asset` 時的 asset 名稱
+const DEFAULT_ASSET: &str = "claude-confirm-{version}-{os}-{arch}.tar.gz";
+/// 依序尋找的 checksum 檔（`sha256sum` 格式），也接受 `<asset>.sha256`
+const CHECKSUM_ASSETS: [&str; 2] = ["SHA256SUMS", "SHA256SUMS.txt"];
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+/// 更新檔的大小上限
+const MAX_ARCHIVE_BYTES: u64 = 256 * 1024 * 1024;
+/// checksum 與簽章檔的大小上限
+const MAX_METADATA_BYTES: u64 = 64 * 1024;
+/// 驗證 release 簽章的 minisign 公鑰，由發布流程在編譯時指定（`CLAUDE_CONFIRM_RELEASE_PUBKEY`）
+///
+/// 設定檔的 `update.public_key` 會取代這把公鑰；兩者都沒有時拒絕更新。
+const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("CLAUDE_CONFIRM_RELEASE_PUBKEY");
+
+const CLI_NAME: &str = "claude-confirm";
+const UI_NAME: &str = "claude-confirm-ui";
+
+/// 一次成功的更新
+#[derive(Debug, Clone)]
+pub struct UpdateOutcome {
+    pub from: Version,
+    pub to: Version,
+    /// 已替換的執行檔
+    pub replaced: Vec<PathBuf>,
+}
+
+/// 更新前的備份（`<data_local_dir>/claude-confirm/rollback/`，只保留上一版）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollbackManifest {
+    /// 備份的版本
+    pub version: String,
+    /// 更新到的版本
+    pub replaced_by: String,
+    pub files: Vec<BackupFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupFile {
+    pub target: PathBuf,
+    pub backup: PathBuf,
+}
+
+/// 更新到頻道中最新的版本
+///
+/// 下載此平台的 asset，驗證 SHA-256 與 minisign 簽章後，同時替換 CLI 與 UI 執行檔。
+/// 沒有簽章公鑰、或找不到任一個執行檔時，在下載前就拒絕更新。
+/// 替換前備份目前的執行檔，任一檔案替換失敗時還原已替換的檔案。
+/// 已是最新版本且未指定 `force` 時回傳 None。
+pub async fn self_update(config: &UpdateConfig, force: bool) -> Result<Option<UpdateOutcome>> {
+    let public_key = public_key(config)?;
+    let targets = locate_binaries()?;
+
+    let check = check(config).await?;
+    let Some((version, release)) = check.latest.clone() else {
+        bail!("目前的更新頻道沒有可用的 release 版本");
+    };
+    if !check.has_update() && !force {
+        return Ok(None);
+    }
+
+    let asset_name = asset_name(config, &version);
+    let asset = release.asset(&asset_name).with_context(|| {
+        format!("release {} 沒有此平台的檔案: {}", release.tag_name, asset_name)
+    })?;
+
+    let client = http_client(DOWNLOAD_TIMEOUT)?;
+    let archive = download(&client, asset, MAX_ARCHIVE_BYTES).await?;
+    verify_checksum(&client, &release, asset, &archive).await?;
+    verify_signature(&client, &release, asset, &archive, &public_key).await?;
+
+    let binaries = tokio::task::spawn_blocking(move || -> Result<Vec<(PathBuf, Vec<u8>)>> {
+        let mut contents = extract(&archive)?;
+        targets
+            .into_iter()
+            .map(|(name, target)| {
+                let data = contents
+                    .remove(&name)
+                    .with_context(|| format!("更新檔中缺少 {}", name))?;
+                Ok((target, data))
+            })
+            .collect()
+    })
+    .await??;
+
+    let from = check.current.clone();
+    let to = version.clone();
+    let replaced = tokio::task::spawn_blocking(move || {
+        replace_all(binaries, &from.to_string(), &to.to_string())
+    })
+    .await??;
+
+    Ok(Some(UpdateOutcome {
+        from: check.current,
+        to: version,
+        replaced,
+    }))
+}
+
+/// 還原上一次更新前的執行檔
+pub fn rollback() -> Result<RollbackManifest> {
+    let manifest = rollback_info().context("沒有可還原的版本")?;
+    for file in &manifest.files {
+        let data = std::fs::read(&file.backup)
+            .with_context(|| format!("讀取備份失敗: {}", file.backup.display()))?;
+        let staged = stage(&file.target, &data)?;
+        swap(&staged, &file.target)?;
+    }
+    if let Some(dir) = rollback_dir() {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+    Ok(manifest)
+}
+
+/// 可還原的上一版，沒有備份時回傳 None
+pub fn rollback_info() -> Option<RollbackManifest> {
+    let content = std::fs::read_to_string(rollback_dir()?.join("manifest.json")).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn rollback_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("claude-confirm").join("rollback"))
+}
+
+/// 此平台的 asset 名稱
+fn asset_name(config: &UpdateConfig, version: &Version) -> String {
+    config
+        .asset
+        .as_deref()
+        .unwrap_or(DEFAULT_ASSET)
+        .replace("{version}", &version.to_string())
+        .replace("{os}", std::env::consts::OS)
+        .replace("{arch}", std::env::consts::ARCH)
+}
+
+/// 驗證簽章用的公鑰：設定檔的 `update.public_key`，沒有時使用編譯時內建的公鑰
+fn public_key(config: &UpdateConfig) -> Result<minisign_verify::PublicKey> {
+    let (key, source) = match config.public_key.as_deref() {
+        Some(key) => (key, "update.public_key"),
+        None => (
+            RELEASE_PUBLIC_KEY.context(
+                "此版本沒有內建簽章公鑰，請在設定檔加上 update.public_key（minisign 公鑰）後再更新",
+            )?,
+            "內建的簽章公鑰",
+        ),
+    };
+    minisign_verify::PublicKey::from_base64(key.trim())
+        .map_err(|e| anyhow!("{} 無效: {}", source, e))
+}
+
+/// 要替換的執行檔：（更新檔中的檔名, 本機路徑），CLI 與 UI 都必須找到
+///
+/// 路徑會解析符號連結，替換實際的檔案（例如 Homebrew 的連結）。
+/// 位於 `.app` bundle 內的執行檔不會替換：改動其中的檔案會破壞 bundle 的程式碼簽章。
+fn locate_binaries() -> Result<Vec<(String, PathBuf)>> {
+    let exe = std::env::current_exe().context("無法取得目前執行檔路徑")?;
+    let exe_name = exe.file_stem().and_then(|name| name.to_str()).unwrap_or_default();
+    let sibling = |name: &str| {
+        exe.parent()
+            .map(|dir| dir.join(format!("{}{}", name, std::env::consts::EXE_SUFFIX)))
+            .filter(|path| path.is_file())
+    };
+
+    let cli = match exe_name {
+        CLI_NAME => Some(exe.clone()),
+        _ => sibling(CLI_NAME).or_else(|| which(CLI_NAME)),
+    };
+    let ui = match exe_name {
+        UI_NAME => Some(exe.clone()),
+        _ => crate::popup::find_ui_command().ok().and_then(|command| {
+            let path = PathBuf::from(&command);
+            if path.is_absolute() {
+                Some(path)
+            } else {
+                which(&command)
+            }
+        }),
+    };
+
+    let mut targets = Vec::new();
+    for (name, path) in [(CLI_NAME, cli), (UI_NAME, ui)] {
+        // 只更新其中一個會讓 CLI 與 UI 的版本不一致
+        let path = path.with_context(|| format!("找不到 {}，無法同時更新 CLI 與 UI", name))?;
+        let path = path.canonicalize().unwrap_or(path);
+        if let Some(bundle) = app_bundle(&path) {
+            bail!(
+                "{} 位於 {} 內，替換會破壞 bundle 的簽章；請下載新版的 claude-confirm.app 取代整個 bundle",
+                name,
+                bundle.display()
+            );
+        }
+        targets.push((format!("{}{}", name, std::env::consts::EXE_SUFFIX), path));
+    }
+    Ok(targets)
+}
+
+/// 包含 `path` 的 `.app` bundle
+fn app_bundle(path: &Path) -> Option<&Path> {
+    path.ancestors()
+        .skip(1)
+        .find(|dir| dir.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("app")))
+}
+
+/// 在 PATH 中尋找執行檔
+fn which(name: &str) -> Option<PathBuf> {
+    let file = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(&file))
+        .find(|path| path.is_file())
+}
+
+/// 下載 asset（只接受 https），超過 `limit` bytes 時中止
+async fn download(client: &reqwest::Client, asset: &ReleaseAsset, limit: u64) -> Result<Vec<u8>> {
+    require_https(&asset.browser_download_url)
+        .with_context(|| format!("{} 的下載網址無效", asset.name))?;
+    if asset.size > limit {
+        bail!("{} 的大小 {} bytes 超過上限 {} bytes", asset.name, asset.size, limit);
+    }
+    let response = client
+        .get(&asset.browser_download_url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("下載 {} 失敗", asset.name))?;
+    read_limited(response, limit)
+        .await
+        .with_context(|| format!("下載 {} 失敗", asset.name))
+}
+
+/// 以 release 中的 `SHA256SUMS`（或 `<asset>.sha256`）驗證下載的檔案，沒有 checksum 時拒絕更新
+async fn verify_checksum(
+    client: &reqwest::Client,
+    release: &Release,
+    asset: &ReleaseAsset,
+    data: &[u8],
+) -> Result<()> {
+    let own = format!("{}.sha256", asset.name);
+    let checksum_asset = CHECKSUM_ASSETS
+        .into_iter()
+        .chain([own.as_str()])
+        .find_map(|name| release.asset(name))
+        .with_context(|| format!("release {} 沒有 SHA256SUMS，拒絕更新", release.tag_name))?;
+
+    let sums = download(client, checksum_asset, MAX_METADATA_BYTES).await?;
+    let sums = String::from_utf8(sums).context("SHA256SUMS 不是 UTF-8 文字")?;
+    let expected = sums
+        .lines()
+        .find_map(|line| {
+            let mut parts = line.split_whitespace();
+            let hash = parts.next()?;
+            match parts.next() {
+                // `sha256sum` 的二進位模式在檔名前加上 `*`
+                Some(name) if name.trim_start_matches('*') == asset.name => Some(hash),
+                None if checksum_asset.name == own => Some(hash),
+                _ => None,
+            }
+        })
+        .with_context(|| format!("{} 中沒有 {} 的 checksum", checksum_asset.name, asset.name))?;
+
+    let actual = format!("{:x}", Sha256::digest(data));
+    if !actual.eq_ignore_ascii_case(expected) {
+        bail!("{} 的 SHA-256 不符（預期 {}，實際 {}）", asset.name, expected, actual);
+    }
+    Ok(())
+}
+
+/// 以 minisign 公鑰驗證 `<asset>.minisig`，沒有簽章時拒絕更新
+async fn verify_signature(
+    client: &reqwest::Client,
+    release: &Release,
+    asset: &ReleaseAsset,
+    data: &[u8],
+    public_key: &minisign_verify::PublicKey,
+) -> Result<()> {
+    let signature_name = format!("{}.minisig", asset.name);
+    let signature_asset = release.asset(&signature_name).with_context(|| {
+        format!("release {} 沒有簽章 {}，拒絕更新", release.tag_name, signature_name)
+    })?;
+    let signature = download(client, signature_asset, MAX_METADATA_BYTES).await?;
+    let signature = String::from_utf8(signature).context("簽章不是 UTF-8 文字")?;
+
+    let signature = minisign_verify::Signature::decode(&signature)
+        .map_err(|e| anyhow!("無法解析簽章 {}: {}", signature_name, e))?;
+    public_key
+        .verify(data, &signature, false)
+        .map_err(|e| anyhow!("{} 的簽章驗證失敗: {}", asset.name, e))
+}
+
+/// 從 tar.gz 取出所有一般檔案（只保留檔名，忽略目錄結構）
+fn extract(archive: &[u8]) -> Result<HashMap<String, Vec<u8>>> {
+    let mut files = HashMap::new();
+    let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(archive));
+    for entry in tar.entries().context("無法讀取更新檔")? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let Some(name) = entry
+            .path()?
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(String::from)
+        else {
+            continue;
+        };
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        files.insert(name, data);
+    }
+    Ok(files)
+}
+
+/// 備份目前的執行檔後逐一替換，失敗時還原已替換的檔案，回傳替換的路徑
+fn replace_all(binaries: Vec<(PathBuf, Vec<u8>)>, from: &str, to: &str) -> Result<Vec<PathBuf>> {
+    // 先把新版本寫到目標旁邊，全部成功後才開始替換
+    let mut staged = Vec::new();
+    for (target, data) in &binaries {
+        match stage(target, data) {
+            Ok(path) => staged.push(path),
+            Err(e) => {
+                remove_staged(&staged);
+                return Err(e);
+            }
+        }
+    }
+
+    let manifest = match backup(&binaries, from, to) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            remove_staged(&staged);
+            return Err(e);
+        }
+    };
+
+    let mut replaced: Vec<PathBuf> = Vec::new();
+    for ((target, _), staged_path) in binaries.iter().zip(&staged) {
+        if let Err(e) = swap(staged_path, target) {
+            remove_staged(&staged);
+            for file in manifest.files.iter().filter(|f| replaced.contains(&f.target)) {
+                if let Err(restore_error) = std::fs::read(&file.backup)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|data| stage(&file.target, &data))
+                    .and_then(|path| swap(&path, &file.target))
+                {
+                    log::error!("還原 {} 失敗: {:#}", file.target.display(), restore_error);
+                }
+            }
+            return Err(e.context(format!("替換 {} 失敗，已還原", target.display())));
+        }
+        replaced.push(target.clone());
+    }
+    Ok(replaced)
+}
+
+/// 刪除尚未替換的暫存檔（已替換的已被 rename 走，刪除失敗可忽略）
+fn remove_staged(staged: &[PathBuf]) {
+    for path in staged {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// 把新內容寫到目標同目錄的暫存檔（同一檔案系統，rename 才是原子操作），沿用目標的權限
+fn stage(target: &Path, data: &[u8]) -> Result<PathBuf> {
+    let dir = target.parent().context("執行檔沒有上層目錄")?;
+    let name = target.file_name().and_then(|name| name.to_str()).unwrap_or(CLI_NAME);
+    let path = dir.join(format!(".{}.{}.new", name, uuid::Uuid::new_v4().simple()));
+
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .with_context(|| format!("無法寫入 {}（可能需要管理員權限）", dir.display()))?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    if let Ok(metadata) = std::fs::metadata(target) {
+        std::fs::set_permissions(&path, metadata.permissions())?;
+    }
+    Ok(path)
+}
+
+/// 複製目前的執行檔到備份目錄並寫入 manifest（覆蓋上一次的備份）
+fn backup(binaries: &[(PathBuf, Vec<u8>)], from: &str, to: &str) -> Result<RollbackManifest> {
+    let dir = rollback_dir().context("無法取得備份目錄")?;
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+
+    let mut files = Vec::new();
+    for (target, _) in binaries {
+        let name = target.file_name().context("執行檔沒有檔名")?;
+        let backup = dir.join(name);
+        std::fs::copy(target, &backup)
+            .with_context(|| format!("備份 {} 失敗", target.display()))?;
+        files.push(BackupFile {
+            target: target.clone(),
+            backup,
+        });
+    }
+
+    let manifest = RollbackManifest {
+        version: from.to_string(),
+        replaced_by: to.to_string(),
+        files,
+    };
+    std::fs::write(dir.join("manifest.json"), serde_json::to_string_pretty(&manifest)?)?;
+    Ok(manifest)
+}
+
+/// 以 rename 原子地把暫存檔換到目標位置
+///
+/// Windows 無法覆蓋執行中的 exe，但可以改名，因此先把目前的檔案移開。
+fn swap(staged: &Path, target: &Path) -> Result<()> {
+    if cfg!(windows) {
+        let old = target.with_extension("old");
+        let _ = std::fs::remove_file(&old);
+        if target.exists() {
+            std::fs::rename(target, &old)?;
+        }
+        if let Err(e) = std::fs::rename(staged, target) {
+            let _ = std::fs::rename(&old, target);
+            return Err(e.into());
+        }
+        return Ok(());
+    }
+    std::fs::rename(staged, target)?;
+    Ok(())
+}